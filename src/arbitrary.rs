@@ -0,0 +1,51 @@
+//! Random generation of valid `PackedFreelist`s for property testing with `quickcheck`.
+
+use quickcheck::{Arbitrary, Gen};
+
+use crate::{AllocationID, PackedFreelist};
+
+/// A randomly generated `PackedFreelist`, along with IDs of its objects and IDs of objects that
+/// were removed from it.
+///
+/// The list is built by replaying a random sequence of insertions and removals, so its internal
+/// state is one that real use can reach, including reused allocations.
+#[derive(Debug, Clone)]
+pub struct ArbitraryFreelist<T> {
+    /// The generated list.
+    pub list: PackedFreelist<T>,
+
+    /// The IDs of every object in the list.
+    pub live: Vec<AllocationID>,
+
+    /// IDs of objects that were removed from the list, which must no longer be valid.
+    pub stale: Vec<AllocationID>,
+}
+
+impl<T: Arbitrary> Arbitrary for ArbitraryFreelist<T> {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let size = std::cmp::max(g.size(), 1);
+        let capacity = usize::arbitrary(g) % size + 1;
+        let operations = usize::arbitrary(g) % (size * 4);
+
+        let mut list = PackedFreelist::with_capacity(capacity);
+        let mut live = Vec::new();
+        let mut stale = Vec::new();
+        for _ in 0..operations {
+            if !live.is_empty() && (list.len() == capacity || bool::arbitrary(g)) {
+                let id = live.swap_remove(usize::arbitrary(g) % live.len());
+                list.remove(id);
+                stale.push(id);
+            } else {
+                live.push(list.insert(T::arbitrary(g)).expect("the list has room for an object"));
+            }
+        }
+
+        Self { list, live, stale }
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for PackedFreelist<T> {
+    fn arbitrary(g: &mut Gen) -> Self {
+        ArbitraryFreelist::arbitrary(g).list
+    }
+}
@@ -0,0 +1,170 @@
+//! A compact binary encoding of a `PackedFreelist` of plain data, preserving IDs and the order in
+//! which free allocations will be reused.
+//!
+//! All integers are little-endian. The encoding is:
+//!  - the capacity and the number of objects, as `u32`s
+//!  - the first and last allocation of the free queue, as `u16`s
+//!  - for each allocation, its current ID as a `u32` and the next allocation in the queue as a `u16`
+//!  - for each object, in packed order, its ID as a `u32`
+//!  - the bytes of the objects, in packed order
+//!
+//! The growth policy, grow callback, generation seed, dense indices and `stats` counts aren't
+//! encoded, so decoded lists have the defaults. Nor are the generations of allocations dropped by
+//! shrinking, so if a decoded list grows, the new allocations start from generation 0.
+
+use crate::{Allocation, AllocationID, GrowHook, GrowthPolicy, PackedFreelist, ALLOC_INDEX_MASK, TOMBSTONE};
+
+/// The kind of problem found while decoding a `PackedFreelist`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// The input ended before the encoded list did.
+    Truncated,
+    /// The input continues after the encoded list.
+    TrailingBytes,
+    /// The encoded capacity exceeds `PackedFreelist::MAX_SIZE`, or the objects exceed the capacity.
+    TooLarge,
+    /// The IDs or the free queue don't describe a valid list.
+    Inconsistent,
+}
+
+/// Indicates that bytes couldn't be decoded into a `PackedFreelist`.
+#[derive(Debug, Clone)]
+pub struct DecodeError {
+    kind: DecodeErrorKind,
+    message: String,
+}
+
+impl DecodeError {
+    fn new(kind: DecodeErrorKind, message: String) -> Self {
+        Self { kind, message }
+    }
+
+    /// Get the kind of problem found.
+    pub fn kind(&self) -> DecodeErrorKind {
+        self.kind
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Failed to decode PackedFreelist: {}", self.message)
+    }
+}
+
+/// Reads the encoding front to back.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() < n {
+            return Err(DecodeError::new(DecodeErrorKind::Truncated,
+                                        format!("expected {} more bytes, found {}", n, self.bytes.len())));
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+impl<T: bytemuck::Pod> PackedFreelist<T> {
+    /// Encode the list, including the IDs of its objects and its queue of free allocations.
+    /// See the module documentation for the format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(12 + self.capacity() * 6 + self.len() * (4 + std::mem::size_of::<T>()));
+        bytes.extend_from_slice(&(self.capacity() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.next_allocation.to_le_bytes());
+        bytes.extend_from_slice(&self.last_allocation.to_le_bytes());
+        for allocation in &self.allocations {
+            bytes.extend_from_slice(&allocation.allocation_id.to_le_bytes());
+            bytes.extend_from_slice(&allocation.next_allocation.to_le_bytes());
+        }
+        for id in &self.object_alloc_ids {
+            bytes.extend_from_slice(&id.to_le_bytes());
+        }
+        bytes.extend_from_slice(bytemuck::cast_slice(&self.objects));
+        bytes
+    }
+
+    /// Decode a list encoded by `encode`. Objects with the same IDs as the encoded ones are in the
+    /// same packed order, and insertions reuse free allocations in the same order.
+    ///
+    /// The input is validated, so malformed input returns an error rather than panicking or
+    /// producing a corrupt list. The bytes don't need to be aligned for `T`.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader { bytes };
+        let capacity = reader.u32()? as usize;
+        let len = reader.u32()? as usize;
+        if capacity > Self::MAX_SIZE || len > capacity {
+            return Err(DecodeError::new(DecodeErrorKind::TooLarge,
+                                        format!("{} objects with capacity {}, but the max size is {}", len, capacity, Self::MAX_SIZE)));
+        }
+
+        let next_allocation = reader.u16()?;
+        let last_allocation = reader.u16()?;
+        let mut allocations = Vec::with_capacity(capacity);
+        for _ in 0..capacity {
+            allocations.push(Allocation {
+                allocation_id: reader.u32()?,
+                object_index: TOMBSTONE,
+                next_allocation: reader.u16()?,
+            });
+        }
+
+        let mut list = Self {
+            objects: Vec::with_capacity(capacity),
+            metadata: Vec::with_capacity(capacity),
+            object_alloc_ids: Vec::with_capacity(capacity),
+            allocations,
+            last_allocation,
+            next_allocation,
+            growth_policy: GrowthPolicy::default(),
+            removals: 0,
+            reorders: 0,
+            insertions: 0,
+            rolled_back: 0,
+            last_inserted: None,
+            on_grow: GrowHook::default(),
+            generation_seed: 0,
+            shrunk_generations: Vec::new(),
+            dense: None,
+        };
+
+        for index in 0..len {
+            let id: AllocationID = reader.u32()?;
+            let allocation = list.allocations.get_mut((id & ALLOC_INDEX_MASK) as usize)
+                .filter(|allocation| allocation.allocation_id == id && allocation.object_index == TOMBSTONE)
+                .ok_or_else(|| DecodeError::new(DecodeErrorKind::Inconsistent,
+                                                format!("object {} has ID {:#x}, which isn't the ID of a distinct allocation", index, id)))?;
+            allocation.object_index = index as u16;
+            list.object_alloc_ids.push(id);
+        }
+
+        let size = std::mem::size_of::<T>();
+        let objects = reader.take(len * size)?;
+        list.objects.extend((0..len).map(|index| bytemuck::pod_read_unaligned::<T>(&objects[index * size..(index + 1) * size])));
+        list.metadata.resize(len, ());
+
+        if !reader.bytes.is_empty() {
+            return Err(DecodeError::new(DecodeErrorKind::TrailingBytes,
+                                        format!("{} bytes remain after the list", reader.bytes.len())));
+        }
+        list.check_invariants().map_err(|report| DecodeError::new(DecodeErrorKind::Inconsistent, report))?;
+
+        Ok(list)
+    }
+}
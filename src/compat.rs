@@ -0,0 +1,154 @@
+//! A `slotmap`-style interface to a `PackedFreelist`, to ease migrating from the `slotmap` crate.
+//!
+//! Method names and signatures follow `slotmap::SlotMap`. The main differences in semantics:
+//!  - Values are kept packed, so iteration order is packed order, which changes on removal.
+//!  - There are at most `PackedFreelist::MAX_SIZE` values, and `insert` panics beyond that.
+//!  - Keys only have 16 bits of generation, so a key can be reused after its slot is reused 2^16
+//!    times.
+
+use std::ops::{Deref, Index, IndexMut};
+
+use crate::{AllocationID, GrowthPolicy, PackedFreelist};
+
+/// A key of a `SlotMap`. Keys are plain IDs of the underlying `PackedFreelist`.
+pub type Key = AllocationID;
+
+/// A map from generation-checked keys to values stored contiguously, growing as needed.
+///
+/// The underlying list can be read through `Deref`.
+#[derive(Debug, Clone)]
+pub struct SlotMap<V> {
+    list: PackedFreelist<V>,
+}
+
+impl<V> SlotMap<V> {
+    /// Constructs a new, empty `SlotMap<V>`.
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Constructs a new, empty `SlotMap<V>` able to hold `capacity` values before growing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut list = PackedFreelist::with_capacity(capacity);
+        list.set_growth_policy(GrowthPolicy::Double);
+        Self { list }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Get the number of values the map can hold before growing.
+    pub fn capacity(&self) -> usize {
+        self.list.capacity()
+    }
+
+    /// Insert a value, returning its key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map already holds `PackedFreelist::MAX_SIZE` values.
+    pub fn insert(&mut self, value: V) -> Key {
+        match self.list.insert(value) {
+            Ok(key) => { key },
+            Err(err) => { panic!("SlotMap can't hold more than {} values: {}", PackedFreelist::<V>::MAX_SIZE, err) },
+        }
+    }
+
+    /// Remove a value, returning it.
+    /// Returns None if the key doesn't correspond to a value in the map.
+    pub fn remove(&mut self, key: Key) -> Option<V> {
+        self.list.remove_with_move(key).map(|removal| removal.value)
+    }
+
+    /// Remove every value. Keys of the removed values become invalid.
+    pub fn clear(&mut self) {
+        self.list.remove_all();
+    }
+
+    /// Returns true if the key corresponds to a value in the map.
+    pub fn contains_key(&self, key: Key) -> bool {
+        self.list.contains(key)
+    }
+
+    /// Get the value for this key, or None if the key doesn't correspond to a value in the map.
+    pub fn get(&self, key: Key) -> Option<&V> {
+        self.list.get(key)
+    }
+
+    /// Get the value for this key mutably, or None if the key doesn't correspond to a value in the
+    /// map.
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut V> {
+        self.list.get_mut(key)
+    }
+
+    /// Iterate over the keys, in packed order.
+    pub fn keys(&self) -> impl ExactSizeIterator<Item = Key> + '_ {
+        self.list.iter_with_ids().map(|(key, _)| key)
+    }
+
+    /// Iterate over the values, in packed order.
+    pub fn values(&self) -> std::slice::Iter<'_, V> {
+        self.list.objects.iter()
+    }
+
+    /// Iterate over the values mutably, in packed order.
+    pub fn values_mut(&mut self) -> std::slice::IterMut<'_, V> {
+        self.list.iter_mut()
+    }
+
+    /// Iterate over the keys and values, in packed order.
+    pub fn iter(&self) -> crate::iter::IterWithIds<'_, V> {
+        self.list.iter_with_ids()
+    }
+
+    /// Iterate over the keys and mutable values, in packed order.
+    pub fn iter_mut(&mut self) -> crate::iter::IterWithIdsMut<'_, V> {
+        self.list.iter_with_ids_mut()
+    }
+
+    /// Consume the map, returning its list of values.
+    pub fn into_inner(self) -> PackedFreelist<V> {
+        self.list
+    }
+}
+
+impl<V> Default for SlotMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Panics if the key doesn't correspond to a value in the map.
+impl<V> Index<Key> for SlotMap<V> {
+    type Output = V;
+
+    fn index(&self, key: Key) -> &Self::Output {
+        match self.get(key) {
+            Some(value) => { value },
+            None => { panic!("{}", self.list.invalid_id_message(key)) },
+        }
+    }
+}
+
+/// Panics if the key doesn't correspond to a value in the map.
+impl<V> IndexMut<Key> for SlotMap<V> {
+    fn index_mut(&mut self, key: Key) -> &mut Self::Output {
+        if !self.list.contains(key) {
+            panic!("{}", self.list.invalid_id_message(key));
+        }
+        self.get_mut(key).unwrap()
+    }
+}
+
+impl<V> Deref for SlotMap<V> {
+    type Target = PackedFreelist<V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.list
+    }
+}
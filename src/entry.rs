@@ -0,0 +1,73 @@
+//! Views of the objects of a `PackedFreelist` along with their IDs.
+
+use crate::AllocationID;
+
+/// An object of a `PackedFreelist` along with its ID.
+///
+/// Created by `PackedFreelist::entries`.
+#[derive(Debug)]
+pub struct Entry<'a, T> {
+    id: AllocationID,
+    value: &'a T,
+}
+
+// Derived impls would needlessly require `T: Clone`.
+impl<'a, T> Clone for Entry<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Entry<'a, T> {}
+
+impl<'a, T> Entry<'a, T> {
+    pub(crate) fn new(id: AllocationID, value: &'a T) -> Self {
+        Self { id, value }
+    }
+
+    /// Get the ID of the object.
+    pub fn id(&self) -> AllocationID {
+        self.id
+    }
+
+    /// Get the object.
+    pub fn value(&self) -> &'a T {
+        self.value
+    }
+}
+
+/// A mutable object of a `PackedFreelist` along with its ID.
+///
+/// Created by `PackedFreelist::entries_mut`.
+#[derive(Debug)]
+pub struct EntryMut<'a, T> {
+    id: AllocationID,
+    value: &'a mut T,
+}
+
+impl<'a, T> EntryMut<'a, T> {
+    pub(crate) fn new(id: AllocationID, value: &'a mut T) -> Self {
+        Self { id, value }
+    }
+
+    /// Get the ID of the object.
+    pub fn id(&self) -> AllocationID {
+        self.id
+    }
+
+    /// Get the object.
+    pub fn value(&self) -> &T {
+        self.value
+    }
+
+    /// Get the object mutably.
+    pub fn value_mut(&mut self) -> &mut T {
+        self.value
+    }
+
+    /// Convert into a mutable reference to the object that lives as long as the entry's borrow of
+    /// the list.
+    pub fn into_value_mut(self) -> &'a mut T {
+        self.value
+    }
+}
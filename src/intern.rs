@@ -0,0 +1,82 @@
+//! Interning on top of a `PackedFreelist`: equal values share a single object and ID.
+
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::ops::Deref;
+
+use crate::{AllocationError, AllocationID, PackedFreelist};
+
+/// A `PackedFreelist` that stores each distinct value once, finding existing values in constant
+/// time through a hashed index of its objects.
+///
+/// The list can be read through `Deref`, but is only modified through the interner, so that the
+/// index stays in sync with the objects.
+#[derive(Debug, Clone)]
+pub struct Interner<T> {
+    list: PackedFreelist<T>,
+
+    /// IDs of the objects, bucketed by the hash of their value.
+    /// IDs are stable across the list's swap-removes, so only insertions and removals update this.
+    buckets: HashMap<u64, Vec<AllocationID>>,
+
+    hasher: RandomState,
+}
+
+impl<T: Hash + Eq> Interner<T> {
+    /// Constructs a new, empty `Interner<T>` able to hold `capacity` distinct values.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            list: PackedFreelist::with_capacity(capacity),
+            buckets: HashMap::with_capacity(capacity),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Insert a value, unless an equal value is already present.
+    /// Returns the ID of the new object, or of the existing one.
+    pub fn insert_unique(&mut self, value: T) -> Result<AllocationID, AllocationError> {
+        let hash = self.hasher.hash_one(&value);
+        let list = &self.list;
+        if let Some(&id) = self.buckets.get(&hash).and_then(|ids| ids.iter().find(|&&id| list[id] == value)) {
+            return Ok(id);
+        }
+
+        let id = self.list.insert(value)?;
+        self.buckets.entry(hash).or_default().push(id);
+        Ok(id)
+    }
+
+    /// Get the ID of the object equal to this value, if there is one.
+    pub fn find(&self, value: &T) -> Option<AllocationID> {
+        let hash = self.hasher.hash_one(value);
+        self.buckets.get(&hash)?.iter().find(|&&id| self.list[id] == *value).cloned()
+    }
+
+    /// Remove an object, returning it.
+    /// Returns None if the ID doesn't correspond to an object in the interner.
+    pub fn remove(&mut self, id: AllocationID) -> Option<T> {
+        let value = self.list.remove(id)?;
+        let hash = self.hasher.hash_one(&value);
+        if let Some(ids) = self.buckets.get_mut(&hash) {
+            ids.retain(|&other| other != id);
+            if ids.is_empty() {
+                self.buckets.remove(&hash);
+            }
+        }
+        Some(value)
+    }
+
+    /// Consume the interner, returning its list of objects.
+    pub fn into_inner(self) -> PackedFreelist<T> {
+        self.list
+    }
+}
+
+impl<T> Deref for Interner<T> {
+    type Target = PackedFreelist<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.list
+    }
+}
@@ -0,0 +1,130 @@
+//! Iterators over the objects of a `PackedFreelist`.
+
+use crate::AllocationID;
+
+/// An iterator over the objects of a `PackedFreelist` along with their positions and IDs, in packed
+/// order. The narrower iterators project out of this one.
+///
+/// Created by `PackedFreelist::iter_indexed`.
+#[derive(Debug, Clone)]
+pub struct IterIndexed<'a, T> {
+    indices: std::ops::Range<usize>,
+    ids: std::slice::Iter<'a, AllocationID>,
+    objects: std::slice::Iter<'a, T>,
+}
+
+impl<'a, T> IterIndexed<'a, T> {
+    pub(crate) fn new(ids: &'a [AllocationID], objects: &'a [T]) -> Self {
+        debug_assert_eq!(ids.len(), objects.len());
+        Self { indices: 0..objects.len(), ids: ids.iter(), objects: objects.iter() }
+    }
+}
+
+impl<'a, T> Iterator for IterIndexed<'a, T> {
+    type Item = (usize, AllocationID, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.indices.next(), self.ids.next(), self.objects.next()) {
+            (Some(index), Some(&id), Some(object)) => { Some((index, id, object)) },
+            _ => { None },
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.objects.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterIndexed<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match (self.indices.next_back(), self.ids.next_back(), self.objects.next_back()) {
+            (Some(index), Some(&id), Some(object)) => { Some((index, id, object)) },
+            _ => { None },
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterIndexed<'a, T> {}
+
+impl<'a, T> std::iter::FusedIterator for IterIndexed<'a, T> {}
+
+/// An iterator over the objects of a `PackedFreelist` along with their IDs, in packed order.
+///
+/// Created by `PackedFreelist::iter_with_ids`.
+#[derive(Debug, Clone)]
+pub struct IterWithIds<'a, T> {
+    inner: IterIndexed<'a, T>,
+}
+
+impl<'a, T> IterWithIds<'a, T> {
+    pub(crate) fn new(inner: IterIndexed<'a, T>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'a, T> Iterator for IterWithIds<'a, T> {
+    type Item = (AllocationID, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, id, object)| (id, object))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterWithIds<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, id, object)| (id, object))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterWithIds<'a, T> {}
+
+impl<'a, T> std::iter::FusedIterator for IterWithIds<'a, T> {}
+
+/// An iterator over the objects of a `PackedFreelist` along with their IDs, yielding mutable
+/// references to the objects, in packed order.
+///
+/// Created by `PackedFreelist::iter_with_ids_mut`.
+#[derive(Debug)]
+pub struct IterWithIdsMut<'a, T> {
+    ids: std::slice::Iter<'a, AllocationID>,
+    objects: std::slice::IterMut<'a, T>,
+}
+
+impl<'a, T> IterWithIdsMut<'a, T> {
+    pub(crate) fn new(ids: &'a [AllocationID], objects: &'a mut [T]) -> Self {
+        debug_assert_eq!(ids.len(), objects.len());
+        Self { ids: ids.iter(), objects: objects.iter_mut() }
+    }
+}
+
+impl<'a, T> Iterator for IterWithIdsMut<'a, T> {
+    type Item = (AllocationID, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.ids.next(), self.objects.next()) {
+            (Some(&id), Some(object)) => { Some((id, object)) },
+            _ => { None },
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.objects.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterWithIdsMut<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match (self.ids.next_back(), self.objects.next_back()) {
+            (Some(&id), Some(object)) => { Some((id, object)) },
+            _ => { None },
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterWithIdsMut<'a, T> {}
+
+impl<'a, T> std::iter::FusedIterator for IterWithIdsMut<'a, T> {}
@@ -1,10 +1,57 @@
 use std::vec::Vec;
-use std::ops::{Index, Deref};
+use std::convert::TryInto;
+use std::ops::{Index, IndexMut, Deref};
+
+#[cfg(feature = "quickcheck")]
+pub mod arbitrary;
+#[cfg(feature = "binary")]
+pub mod binary;
+pub mod compat;
+pub mod entry;
+pub mod intern;
+pub mod iter;
+pub mod pool;
+
+use iter::{IterIndexed, IterWithIds, IterWithIdsMut};
 
 /// Indicates an error when attempting to allocate an object
 #[derive(Debug, Clone)]
 pub struct AllocationError {
-    allocation_index: u16,
+    kind: AllocationErrorKind,
+
+    /// The number of objects in the list at the time of the error.
+    len: usize,
+
+    /// The capacity of the list at the time of the error.
+    capacity: usize,
+}
+
+/// The cause of an `AllocationError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationErrorKind {
+    /// There wasn't enough capacity for the objects, and the growth policy couldn't provide it.
+    AtCapacity,
+
+    /// The queue of free allocations led outside of the list. This indicates a bug in the list.
+    CorruptFreeList,
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl AllocationError {
+    /// The cause of the error.
+    pub fn kind(&self) -> AllocationErrorKind {
+        self.kind
+    }
+
+    /// The number of objects in the list when the error occurred.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The capacity of the list when the error occurred.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
 }
 
 impl std::error::Error for AllocationError {
@@ -16,7 +63,70 @@ impl std::error::Error for AllocationError {
 
 impl std::fmt::Display for AllocationError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Failed to acquire allocation with index {}", self.allocation_index)
+        let cause = match self.kind {
+            AllocationErrorKind::AtCapacity => { "list is at capacity" },
+            AllocationErrorKind::CorruptFreeList => { "free list is corrupt" },
+        };
+        write!(f, "Failed to acquire allocation: {} with {} of {} objects", cause, self.len, self.capacity)
+    }
+}
+
+/// Indicates that a list could not be rolled back to a checkpoint
+#[derive(Debug, Clone)]
+pub struct RollbackError {
+    checkpoint: Checkpoint,
+}
+
+impl std::error::Error for RollbackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+impl std::fmt::Display for RollbackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Failed to roll back to checkpoint with length {}: the list changed by more than insertions since it was taken", self.checkpoint.len)
+    }
+}
+
+/// Indicates that a list could not reserve capacity, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReserveError {
+    /// The capacity required would exceed `PackedFreelist::MAX_SIZE`.
+    ExceedsMaxSize {
+        /// The capacity required.
+        requested: usize,
+        /// The largest capacity allowed.
+        max: usize,
+    },
+
+    /// The memory for the capacity couldn't be allocated.
+    AllocFailed(std::collections::TryReserveError),
+}
+
+impl std::error::Error for ReserveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReserveError::ExceedsMaxSize { .. } => { None },
+            ReserveError::AllocFailed(err) => { Some(err) },
+        }
+    }
+}
+
+impl std::fmt::Display for ReserveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReserveError::ExceedsMaxSize { requested, max } => {
+                write!(f, "Failed to reserve capacity: {} objects requested but the max size is {}", requested, max)
+            },
+            ReserveError::AllocFailed(err) => { write!(f, "Failed to reserve capacity: {}", err) },
+        }
+    }
+}
+
+impl From<std::collections::TryReserveError> for ReserveError {
+    fn from(err: std::collections::TryReserveError) -> Self {
+        ReserveError::AllocFailed(err)
     }
 }
 
@@ -39,21 +149,218 @@ struct Allocation {
 }
 
 /// Used to extract the allocation index from an object ID.
+#[allow(clippy::legacy_numeric_constants)]
 const ALLOC_INDEX_MASK: AllocationID = u16::max_value() as AllocationID;
 
+/// Used to extract the generation from an object ID.
+const GENERATION_SHIFT: u32 = 16;
+
 /// Used to mark an allocation as owning no object. This system's sentinel value.
+#[allow(clippy::legacy_numeric_constants)]
 const TOMBSTONE: u16 = u16::max_value();
 
+/// How a `PackedFreelist` grows when it doesn't have room for an insertion.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum GrowthPolicy {
+    /// Never grow: insertions into a full list fail.
+    #[default]
+    Fixed,
+    /// Double the capacity. An empty capacity grows to 1.
+    Double,
+    /// Add a fixed number of slots.
+    Additive(usize),
+    /// Compute the new capacity from the current one.
+    Custom(fn(usize) -> usize),
+}
+
+impl GrowthPolicy {
+    /// The capacity to grow to from `capacity`, before clamping to the maximum size.
+    fn grow(self, capacity: usize) -> usize {
+        match self {
+            GrowthPolicy::Fixed => { capacity },
+            GrowthPolicy::Double => { std::cmp::max(capacity * 2, 1) },
+            GrowthPolicy::Additive(n) => { capacity.saturating_add(n) },
+            GrowthPolicy::Custom(f) => { f(capacity) },
+        }
+    }
+}
+
+/// The ID of the next object allocated by the allocation of this ID.
+///
+/// Only the generation is incremented, wrapping to 0 after `u16::MAX`, so the allocation index is
+/// never disturbed. A wrapped generation means IDs of this allocation start being reused.
+fn next_generation(id: AllocationID) -> AllocationID {
+    let generation = (id >> GENERATION_SHIFT) as u16;
+    (AllocationID::from(generation.wrapping_add(1)) << GENERATION_SHIFT) | (id & ALLOC_INDEX_MASK)
+}
+
+/// Returns true if both IDs are for the same allocation, whatever their generations.
+/// An ID that went stale because its allocation was reused is for the same allocation as the new ID.
+pub fn same_slot(a: AllocationID, b: AllocationID) -> bool {
+    a & ALLOC_INDEX_MASK == b & ALLOC_INDEX_MASK
+}
+
+/// Returns whether `a` is from a later generation of its allocation than `b`, or None if they're
+/// for different allocations.
+///
+/// Generations wrap around, so `a` counts as newer if it's less than 2^15 generations ahead of
+/// `b`. This is correct as long as the allocation wasn't reused more than that in between.
+pub fn is_newer(a: AllocationID, b: AllocationID) -> Option<bool> {
+    if !same_slot(a, b) {
+        return None;
+    }
+    let ahead = ((a >> GENERATION_SHIFT) as u16).wrapping_sub((b >> GENERATION_SHIFT) as u16);
+    Some(ahead != 0 && ahead < 0x8000)
+}
+
+/// An object removed from a `PackedFreelist`, and the effect of its removal on the packing.
+/// See `PackedFreelist::remove_with_move`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Removal<T> {
+    /// The removed object.
+    pub value: T,
+
+    /// The ID and new position of the object that was moved into the removed object's position,
+    /// or None if the removed object was the last one and nothing moved.
+    pub moved: Option<(AllocationID, usize)>,
+}
+
+/// The state of one allocation of a `PackedFreelist`, for inspecting its bookkeeping.
+/// See `PackedFreelist::debug_slots`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotInfo {
+    /// The allocation holds an object.
+    Live {
+        /// The ID of the object.
+        id: AllocationID,
+        /// The position of the object in packed order.
+        object_index: usize,
+    },
+    /// The allocation is free.
+    Free {
+        /// The ID of the object the allocation last held, or its initial ID if it never held one.
+        id: AllocationID,
+        /// The allocation queued after this one. This is stale for the last allocation in the queue.
+        next: usize,
+    },
+}
+
+/// A saved state of a `PackedFreelist` that it can be rolled back to, undoing insertions.
+/// See `PackedFreelist::checkpoint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The number of objects when the checkpoint was taken.
+    len: usize,
+
+    /// The number of removals when the checkpoint was taken.
+    removals: u64,
+
+    /// The number of reorders when the checkpoint was taken.
+    reorders: u64,
+}
+
+/// Lifetime counts of the insertions into and removals from a `PackedFreelist`.
+/// See `PackedFreelist::stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of objects ever inserted.
+    pub total_inserts: u64,
+
+    /// The number of objects ever removed, including those dropped by `reset` and `rollback`.
+    pub total_removes: u64,
+
+    /// The number of objects in the list.
+    pub len: usize,
+
+    /// The capacity of the list.
+    pub capacity: usize,
+}
+
+/// Small integers assigned to objects for their whole lifetime, recycled once objects are removed.
+/// See `PackedFreelist::enable_dense_indices`.
+#[derive(Debug, Clone, Default)]
+struct DenseIndices {
+    /// The dense index of the object of each allocation, indexed by allocation index. Entries of free
+    /// allocations are stale. Grows as allocations are assigned, rather than with the capacity.
+    of_allocation: Vec<u16>,
+
+    /// Dense indices released by removals, reused last in, first out.
+    free: Vec<u16>,
+
+    /// The number of dense indices ever handed out, so the next fresh one.
+    next: u16,
+}
+
+impl DenseIndices {
+    fn assign(&mut self, alloc_index: usize) {
+        let dense_index = match self.free.pop() {
+            Some(dense_index) => { dense_index },
+            None => {
+                self.next += 1;
+                self.next - 1
+            },
+        };
+        if self.of_allocation.len() <= alloc_index {
+            self.of_allocation.resize(alloc_index + 1, TOMBSTONE);
+        }
+        self.of_allocation[alloc_index] = dense_index;
+    }
+
+    fn release(&mut self, alloc_index: usize) {
+        self.free.push(self.of_allocation[alloc_index]);
+    }
+
+    /// Keep the dense index of an object whose allocation changed.
+    fn moved(&mut self, from: usize, to: usize) {
+        if self.of_allocation.len() <= to {
+            self.of_allocation.resize(to + 1, TOMBSTONE);
+        }
+        self.of_allocation[to] = self.of_allocation[from];
+    }
+}
+
+/// A callback invoked with the old and new capacity whenever a `PackedFreelist` grows.
+/// See `PackedFreelist::set_on_grow`.
+#[derive(Default)]
+struct GrowHook(Option<Box<dyn FnMut(usize, usize) + Send + Sync>>);
+
+impl std::fmt::Debug for GrowHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(if self.0.is_some() { "GrowHook(Some(..))" } else { "GrowHook(None)" })
+    }
+}
+
+// The hook is only ever called through `&mut`, while growing, so it can't be observed in a broken
+// state through a shared reference after a panic. Without these, the hook would make every list
+// unusable with `catch_unwind`.
+impl std::panic::RefUnwindSafe for GrowHook {}
+impl std::panic::UnwindSafe for GrowHook {}
+
+/// Closures can't be cloned, so clones start without a hook.
+impl Clone for GrowHook {
+    fn clone(&self) -> Self {
+        GrowHook(None)
+    }
+}
+
 /// A data structure that provides constant time insertions and deletions and that elements are
 /// contiguous in memory.
-#[derive(Debug, Clone)]
-pub struct PackedFreelist<T> {
+///
+/// Each object can carry metadata of type `M`, such as a layer mask, which is kept apart from the
+/// objects but moves along with its object. By default there's no metadata, which costs nothing.
+#[derive(Clone)]
+pub struct PackedFreelist<T, M = ()> {
     /// Storage for objects
     /// Objects are contiguous, and always packed to the start of the storage.
     /// Objects can be relocated in this storage thanks to the separate list of allocations.
     objects: Vec<T>,
 
-    /// The index in the allocations array for the next allocation to allocate after this one.
+    /// The metadata of the object at each position of the objects array.
+    /// Every change to the objects is mirrored here, so that the two stay in lockstep.
+    metadata: Vec<M>,
+
+    /// The ID of the object at each position of the objects array.
+    /// Grows and shrinks along with the objects, rather than being allocated up front.
     object_alloc_ids: Vec<AllocationID>,
 
     /// FIFO queue to allocate objects with least ID reuse possible
@@ -67,28 +374,152 @@ pub struct PackedFreelist<T> {
 
     /// The next index struct to use for an allocation.
     next_allocation: u16,
+
+    /// How to grow when inserting into a full list.
+    growth_policy: GrowthPolicy,
+
+    /// The number of objects ever removed, used to detect removals since a checkpoint.
+    removals: u64,
+
+    /// The number of times objects were moved to other positions or allocations, other than by
+    /// removals, used to detect reorders since a checkpoint.
+    reorders: u64,
+
+    /// The number of objects ever inserted.
+    insertions: u64,
+
+    /// The number of objects ever dropped by `rollback`. These aren't counted in `removals`, so
+    /// that rolling back to one checkpoint doesn't prevent rolling back to an earlier one.
+    rolled_back: u64,
+
+    /// The ID of the most recently inserted object. It may no longer be valid.
+    last_inserted: Option<AllocationID>,
+
+    /// Called whenever the capacity grows.
+    on_grow: GrowHook,
+
+    /// The generation of allocations that were never used to allocate an object.
+    generation_seed: u16,
+
+    /// The generations of allocations dropped by shrinking, starting with the allocation at index
+    /// `capacity`, so they're restored rather than reset if the list grows back.
+    shrunk_generations: Vec<u16>,
+
+    /// Lifetime stable indices of the objects, if enabled.
+    dense: Option<DenseIndices>,
 }
 
 impl<T> PackedFreelist<T> {
+    /// Constructs a new, empty `PackedFreelist<T>` with the specified capacity.
+    ///
+    /// The freelist will be able to hold exactly `capacity` elements without reallocating. A
+    /// capacity of 0 is allowed, and the list can then only hold objects once it grows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` exceeds `MAX_SIZE`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_metadata(capacity)
+    }
+
+    /// Constructs a new, empty `PackedFreelist<T>` with the specified capacity, whose allocations
+    /// start at generation `generation_seed` rather than 0. The first object allocated by each
+    /// allocation therefore has generation `generation_seed + 1`, as do objects allocated by
+    /// allocations added when the list grows, or after a `reset`.
+    ///
+    /// This makes ID sequences reproducible yet distinct from those of unseeded lists, such as to
+    /// give lists on different nodes disjoint generation ranges.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` exceeds `MAX_SIZE`, or if `generation_seed` is `u16::MAX`, since the
+    /// first generation would then wrap around to 0.
+    pub fn with_capacity_seeded(capacity: usize, generation_seed: u16) -> Self {
+        Self::with_capacity_seeded_and_metadata(capacity, generation_seed)
+    }
+
+    /// Constructs a `PackedFreelist<T>` holding just this object, with no spare capacity.
+    /// Returns the list along with the object's ID.
+    ///
+    /// The object is stored on the heap like the objects of any other list. There's no inline
+    /// storage for tiny lists: the objects are kept in a `Vec`, which `TryFrom<Vec<T>>` and
+    /// `into_sorted_vec` move in and out without copying. Only lists with capacity 0 allocate
+    /// nothing.
+    pub fn singleton(value: T) -> (Self, AllocationID) {
+        Self::singleton_with_meta(value, ())
+    }
+
+    /// Constructs a full `PackedFreelist<T>` holding the values of the map, along with a map from
+    /// each key to the ID of its value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map holds more than `MAX_SIZE` values.
+    pub fn from_map<K: Eq + std::hash::Hash>(map: std::collections::HashMap<K, T>) -> (Self, std::collections::HashMap<K, AllocationID>) {
+        Self::from_map_and_metadata(map)
+    }
+}
+
+impl<T, M> PackedFreelist<T, M> {
     /// The maximum size allowed by this implementation of a PackedFreelist.
     pub const MAX_SIZE: usize = (TOMBSTONE - 1) as usize;
 
-    /// Constructs a new, empty `PackedFreelist<T>` with the specified capacity.
+    /// The largest capacity a list can have, `MAX_SIZE`.
+    pub fn max_capacity() -> usize {
+        Self::MAX_SIZE
+    }
+
+    /// Get the largest number of objects this list can hold, for code that validates sizes through
+    /// a reference rather than the type. This is `MAX_SIZE`.
+    pub fn max_size(&self) -> usize {
+        Self::MAX_SIZE
+    }
+
+    /// Returns true if the list holds `max_size` objects, so no more can be inserted whatever the
+    /// growth policy.
+    pub fn is_at_max_size(&self) -> bool {
+        self.len() >= self.max_size()
+    }
+
+    /// Returns true if a list can hold `n` objects, so `with_capacity(n)` won't panic for being too
+    /// large.
+    pub fn can_hold(n: usize) -> bool {
+        n <= Self::MAX_SIZE
+    }
+
+    /// Constructs a new, empty `PackedFreelist<T, M>` with the specified capacity, whose objects
+    /// each carry metadata of type `M`. See `with_capacity`.
     ///
-    /// The freelist will be able to hold exactly `capacity` elements without reallocating.
-    pub fn with_capacity(capacity: usize) -> Self {
+    /// # Panics
+    ///
+    /// Panics if `capacity` exceeds `MAX_SIZE`.
+    pub fn with_capacity_and_metadata(capacity: usize) -> Self {
         assert!(capacity <= Self::MAX_SIZE, "PackedFreelist is too large. Max size is {}.", Self::MAX_SIZE);
 
+        // Allocation indices stay below `MAX_SIZE`, so linking each to the next can't overflow a u16
+        // and the last link, to `capacity`, is replaced below to make the queue circular.
         let mut r = Self {
             objects: Vec::with_capacity(capacity),
-            object_alloc_ids: vec![0; capacity],
+            metadata: Vec::with_capacity(capacity),
+            object_alloc_ids: Vec::with_capacity(capacity),
             allocations: (0..capacity as u16).map(|i| Allocation {
                 allocation_id: AllocationID::from(i),
                 object_index: TOMBSTONE,
                 next_allocation: i + 1
             }).collect(),
-            last_allocation: (capacity - 1) as u16,
-            next_allocation: 0
+            // An empty list has an empty queue, whose ends are never read.
+            last_allocation: capacity.saturating_sub(1) as u16,
+            next_allocation: 0,
+            growth_policy: GrowthPolicy::default(),
+            removals: 0,
+            reorders: 0,
+            insertions: 0,
+            rolled_back: 0,
+            last_inserted: None,
+            on_grow: GrowHook::default(),
+            generation_seed: 0,
+            shrunk_generations: Vec::new(),
+            dense: None,
         };
 
         if capacity > 0 {
@@ -98,8 +529,229 @@ impl<T> PackedFreelist<T> {
         r
     }
 
+    /// Constructs a new, empty `PackedFreelist<T, M>` with the specified capacity and generation
+    /// seed, whose objects each carry metadata of type `M`. See `with_capacity_seeded`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` exceeds `MAX_SIZE`, or if `generation_seed` is `u16::MAX`.
+    pub fn with_capacity_seeded_and_metadata(capacity: usize, generation_seed: u16) -> Self {
+        assert!(generation_seed < u16::MAX, "PackedFreelist generation seed must be less than {}.", u16::MAX);
+
+        let mut r = Self::with_capacity_and_metadata(capacity);
+        r.generation_seed = generation_seed;
+        for (index, allocation) in r.allocations.iter_mut().enumerate() {
+            allocation.allocation_id = (AllocationID::from(generation_seed) << GENERATION_SHIFT) | index as AllocationID;
+        }
+        r
+    }
+
+    /// Constructs a `PackedFreelist<T, M>` holding just this object along with its metadata, with
+    /// no spare capacity. See `singleton`.
+    pub fn singleton_with_meta(value: T, meta: M) -> (Self, AllocationID) {
+        let mut r = Self::with_capacity_and_metadata(1);
+        let id = r.insert_with_meta(value, meta).expect("a list with capacity 1 has room for an object");
+        (r, id)
+    }
+
+    /// Constructs a full `PackedFreelist<T, M>` holding the values of the map, each with default
+    /// metadata, along with a map from each key to the ID of its value. See `from_map`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map holds more than `MAX_SIZE` values.
+    pub fn from_map_and_metadata<K: Eq + std::hash::Hash>(map: std::collections::HashMap<K, T>) -> (Self, std::collections::HashMap<K, AllocationID>) where M: Default {
+        let mut r = Self::with_capacity_and_metadata(map.len());
+        let ids = map.into_iter()
+            .map(|(key, value)| (key, r.insert(value).expect("the list has room for every value")))
+            .collect();
+        (r, ids)
+    }
+
+    /// Builds a full freelist around already-packed objects, as if each had been inserted in order.
+    fn from_vec(objects: Vec<T>) -> Self where M: Default {
+        let len = objects.len();
+        assert!(len <= Self::MAX_SIZE, "PackedFreelist is too large. Max size is {}.", Self::MAX_SIZE);
+
+        let allocations: Vec<Allocation> = (0..len as u16).map(|i| Allocation {
+            allocation_id: 0x10000 | AllocationID::from(i),
+            object_index: i,
+            next_allocation: i + 1
+        }).collect();
+
+        Self {
+            metadata: objects.iter().map(|_| M::default()).collect(),
+            objects,
+            object_alloc_ids: allocations.iter().map(|a| a.allocation_id).collect(),
+            last_inserted: allocations.last().map(|a| a.allocation_id),
+            allocations,
+            last_allocation: len.saturating_sub(1) as u16,
+            next_allocation: 0,
+            growth_policy: GrowthPolicy::default(),
+            removals: 0,
+            reorders: 0,
+            insertions: len as u64,
+            rolled_back: 0,
+            on_grow: GrowHook::default(),
+            generation_seed: 0,
+            shrunk_generations: Vec::new(),
+            dense: None,
+        }
+    }
+
+    /// Set how the list grows when inserting into it while it's full.
+    ///
+    /// Growth never exceeds `MAX_SIZE`. The default, `GrowthPolicy::Fixed`, never grows.
+    pub fn set_growth_policy(&mut self, policy: GrowthPolicy) {
+        self.growth_policy = policy;
+    }
+
+    /// Get how the list grows when inserting into it while it's full.
+    pub fn growth_policy(&self) -> GrowthPolicy {
+        self.growth_policy
+    }
+
+    /// Set a callback to call with the old and new capacity whenever the capacity grows, such as to
+    /// monitor how large pools get. It replaces any previous callback.
+    ///
+    /// There is no callback by default. Clones of the list start without one.
+    pub fn set_on_grow(&mut self, f: impl FnMut(usize, usize) + Send + Sync + 'static) {
+        self.on_grow = GrowHook(Some(Box::new(f)));
+    }
+
+    /// Grow the capacity, if needed, so that `additional` more objects can be inserted without
+    /// growing. Unlike growth on insertion, the growth policy is ignored: the capacity grows to
+    /// exactly `len + additional`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity would exceed `MAX_SIZE`.
+    pub fn reserve(&mut self, additional: usize) {
+        self.reserve_and_report(additional);
+    }
+
+    /// Reserve storage for at least `additional` more objects beyond `len`, without adding
+    /// allocations, so the capacity is unchanged.
+    ///
+    /// The objects don't move in memory while the list grows within the reserved storage, so a
+    /// pointer from `as_ptr` stays valid across insertions as long as the capacity they grow the
+    /// list to still fits in it. Growth beyond the reserved storage, such as doubling past it,
+    /// reallocates the objects. In contrast, `reserve` adds allocations, whose storage is only
+    /// reserved as tightly as the capacity.
+    pub fn reserve_objects(&mut self, additional: usize) {
+        self.objects.reserve_exact(additional);
+    }
+
+    /// Reserve like `reserve`, but return an error rather than panicking if the capacity would exceed
+    /// `MAX_SIZE` or the memory can't be allocated. The list is unchanged on error, though some of
+    /// its storage may have been reserved.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), ReserveError> {
+        let len = self.len();
+        let requested = len.saturating_add(additional);
+        if requested > Self::MAX_SIZE {
+            return Err(ReserveError::ExceedsMaxSize { requested, max: Self::MAX_SIZE });
+        }
+        if requested <= self.capacity() {
+            return Ok(());
+        }
+
+        // With the memory reserved up front, growing can't fail.
+        self.objects.try_reserve_exact(requested - len)?;
+        self.metadata.try_reserve_exact(requested - len)?;
+        self.object_alloc_ids.try_reserve_exact(requested - len)?;
+        self.allocations.try_reserve_exact(requested - self.capacity())?;
+        self.grow_to(requested);
+        self.assert_valid();
+        Ok(())
+    }
+
+    /// Reserve like `reserve`, returning the range of allocation indices that were added, which is
+    /// empty if the list didn't grow.
+    ///
+    /// The new allocations are free and queued after the allocations that were already free, in
+    /// order, so they're allocated only once those are used up.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity would exceed `MAX_SIZE`.
+    pub fn reserve_and_report(&mut self, additional: usize) -> std::ops::Range<usize> {
+        let old_capacity = self.capacity();
+        let required = self.len().saturating_add(additional);
+        assert!(required <= Self::MAX_SIZE, "PackedFreelist is too large. Max size is {}.", Self::MAX_SIZE);
+
+        if required > old_capacity {
+            self.grow_to(required);
+            self.assert_valid();
+        }
+        old_capacity..self.capacity()
+    }
+
+    /// Reserve like `reserve`, but without exceeding `MAX_SIZE`: if `additional` more objects can't
+    /// fit, grow to `MAX_SIZE` instead of panicking. Returns the number of allocations added.
+    pub fn reserve_saturating(&mut self, additional: usize) -> usize {
+        let required = std::cmp::min(self.len().saturating_add(additional), Self::MAX_SIZE);
+        self.reserve_and_report(required - self.len()).len()
+    }
+
+    /// Reduce the capacity towards `target`, releasing the memory of the freed allocations, and
+    /// return the resulting capacity.
+    ///
+    /// Only trailing free allocations can be dropped, since an object's allocation can't change
+    /// without changing its ID. The capacity therefore never drops below `len`, nor below the
+    /// highest allocation still in use. Free allocations that survive keep their place in the queue.
+    ///
+    /// The generations of the dropped allocations are kept, so if the list grows back, the
+    /// allocations carry on from them rather than restarting from the generation seed. IDs of
+    /// objects removed before shrinking therefore stay invalid, as they would without shrinking.
+    pub fn shrink_capacity_to(&mut self, target: usize) -> usize {
+        let highest_live = self.object_alloc_ids.iter()
+            .map(|&id| (id & ALLOC_INDEX_MASK) as usize + 1)
+            .max()
+            .unwrap_or(0);
+        let capacity = std::cmp::max(target, highest_live);
+        if capacity >= self.capacity() {
+            return self.capacity();
+        }
+
+        let free: Vec<u16> = self.free_queue().into_iter().filter(|&i| (i as usize) < capacity).collect();
+        let dropped: Vec<u16> = self.allocations[capacity..].iter()
+            .map(|allocation| (allocation.allocation_id >> GENERATION_SHIFT) as u16)
+            .collect();
+        self.shrunk_generations.splice(0..0, dropped);
+        // Relinking the free queue breaks the chain rollback relies on.
+        self.reorders += 1;
+        self.allocations.truncate(capacity);
+        self.allocations.shrink_to_fit();
+        self.object_alloc_ids.shrink_to(capacity);
+        self.objects.shrink_to(capacity);
+        self.metadata.shrink_to(capacity);
+        self.link_free_queue(&free);
+        self.assert_valid();
+
+        capacity
+    }
+
+    /// Reduce the capacity to the larger of `min_capacity` and `len`, like `Vec::shrink_to`, as far as
+    /// the allocations in use allow. See `shrink_capacity_to`.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.shrink_capacity_to(std::cmp::max(min_capacity, self.len()));
+    }
+
+    /// Reduce the capacity as far as the allocations in use allow, like `Vec::shrink_to_fit`.
+    /// See `shrink_capacity_to`.
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
     /// Query for an ID.
     /// Returns true if the ID corresponds to an object in the list. False otherwise.
+    ///
+    /// Any `u32` is accepted without panicking, so untrusted IDs can be checked. The allocation index
+    /// is masked to 16 bits before use, and indices at or beyond the capacity, including the
+    /// `TOMBSTONE` index, are out of range since the capacity is at most `MAX_SIZE`.
+    ///
+    /// This reads a single allocation, which holds both the current ID and whether it's in use, so
+    /// it takes constant time without a separate record of which allocations are live.
     pub fn contains(&self, id: AllocationID) -> bool {
         let allocation = self.allocations.get((id & ALLOC_INDEX_MASK) as usize);
 
@@ -109,102 +761,1536 @@ impl<T> PackedFreelist<T> {
         }
     }
 
-    /// Insert an object
-    pub fn insert(&mut self, value: T) -> Result<AllocationID, AllocationError> {
-        let allocation = self.insert_alloc();
+    /// Query for several IDs, stopping at the first one not in the list.
+    /// Returns true if every ID corresponds to an object in the list, including when there are none.
+    pub fn contains_all<I: IntoIterator<Item = AllocationID>>(&self, ids: I) -> bool {
+        ids.into_iter().all(|id| self.contains(id))
+    }
+
+    /// Query for several IDs, stopping at the first one in the list.
+    /// Returns true if any ID corresponds to an object in the list.
+    pub fn contains_any<I: IntoIterator<Item = AllocationID>>(&self, ids: I) -> bool {
+        ids.into_iter().any(|id| self.contains(id))
+    }
 
-        match allocation {
-            Ok(allocation) => {
-                let object_index = allocation.object_index as usize;
-                let allocation_id = allocation.allocation_id;
-                match self.objects.get_mut(object_index) {
-                    None => self.objects.push(value),
-                    Some(e) => *e = value,
-                }
-                Ok(allocation_id)
+    /// Collect the IDs that correspond to objects in the list, in the order given.
+    pub fn filter_live<I: IntoIterator<Item = AllocationID>>(&self, ids: I) -> Vec<AllocationID> {
+        ids.into_iter().filter(|&id| self.contains(id)).collect()
+    }
+
+    /// Get the current position in the packed objects of the object with this ID.
+    /// Returns None if the ID doesn't correspond to an object in the list.
+    ///
+    /// Positions are not stable: removing any object moves the last object into the removed
+    /// object's position, so this must be queried again after every removal.
+    pub fn index_of(&self, id: AllocationID) -> Option<usize> {
+        match self.allocations.get((id & ALLOC_INDEX_MASK) as usize) {
+            Some(allocation) if allocation.allocation_id == id && allocation.object_index != TOMBSTONE => {
+                Some(allocation.object_index as usize)
             },
-            Err(err) => { Err(err) },
+            _ => { None },
         }
     }
 
-    /// Remove an object
-    pub fn remove(&mut self, id: AllocationID) {
-        match self.allocations.get((id & ALLOC_INDEX_MASK) as usize) {
-            None => { panic!("oh god") },
-            Some(allocation) => {
-                let last_index = (allocation.allocation_id & ALLOC_INDEX_MASK) as u16;
-                match self.objects.get(allocation.object_index as usize) {
-                    None => { panic!("no no no no")},
-                    Some(_object) => {
-                        let last = self.objects.len() - 1;
-                        if allocation.object_index as usize != last {
-                            self.objects.swap(last, allocation.object_index as usize);
-                            self.object_alloc_ids[allocation.object_index as usize] = self.object_alloc_ids[last];
-                            let alloc_index = (self.object_alloc_ids[allocation.object_index as usize] & ALLOC_INDEX_MASK) as usize;
-                            self.allocations[alloc_index].object_index = allocation.object_index;
-                        }
-                    },
-                }
+    /// Get the object at this position in packed order, or None if the position is out of bounds.
+    /// Positions aren't stable, since removals move the last object into the removed object's
+    /// position. Indexing the list is by ID, not position.
+    pub fn get_at(&self, index: usize) -> Option<&T> {
+        self.objects.get(index)
+    }
 
-                self.objects.pop();
+    /// Get the object at this position in packed order mutably. See `get_at`.
+    pub fn get_at_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.objects.get_mut(index)
+    }
 
-                self.allocations[self.last_allocation as usize].next_allocation = last_index;
-                self.last_allocation = last_index;
-            },
-        }
+    /// Get the ID of the object currently at this position in the packed objects.
+    /// Returns None if the position is out of bounds.
+    pub fn handle_at(&self, index: usize) -> Option<AllocationID> {
+        self.object_alloc_ids.get(index).cloned()
+    }
 
-        self.allocations.get_mut((id & ALLOC_INDEX_MASK) as usize).and_then(|a| {
-            a.object_index = TOMBSTONE;
-            Some(a)
-        });
+    /// Get the object with this ID.
+    /// Returns None if the ID doesn't correspond to an object in the list, such as a stale ID whose
+    /// object was removed, rather than panicking like indexing does.
+    pub fn get(&self, id: AllocationID) -> Option<&T> {
+        self.index_of(id).map(|index| &self.objects[index])
     }
 
-    pub fn len(&self) -> usize {
-        self.objects.len()
+    /// Get the object with this ID mutably, to modify it in place without changing its ID. See `get`.
+    pub fn get_mut(&mut self, id: AllocationID) -> Option<&mut T> {
+        let index = self.index_of(id)?;
+        Some(&mut self.objects[index])
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.objects.is_empty()
+    /// Get the metadata of the object with this ID.
+    /// Returns None if the ID doesn't correspond to an object in the list.
+    pub fn meta(&self, id: AllocationID) -> Option<&M> {
+        self.index_of(id).map(|index| &self.metadata[index])
     }
 
-    /// Get maximum number of elements
-    pub fn capacity(&self) -> usize {
-        self.objects.capacity()
+    /// Get the metadata of the object with this ID mutably.
+    /// Returns None if the ID doesn't correspond to an object in the list.
+    pub fn meta_mut(&mut self, id: AllocationID) -> Option<&mut M> {
+        let index = self.index_of(id)?;
+        Some(&mut self.metadata[index])
     }
 
-    /// Internal allocation logic
-    fn insert_alloc(&mut self) -> Result<&Allocation, AllocationError> {
-        let len = self.len();
-        if len >= self.capacity() {
-            return Err(AllocationError { allocation_index: (len + 1) as u16 });
-        }
+    /// Get the metadata of every object, in packed order, so position `i` holds the metadata of the
+    /// object at position `i`.
+    pub fn metadata(&self) -> &[M] {
+        &self.metadata
+    }
+
+    /// Get a clone of the object with this ID, so that the list can be modified straight after.
+    /// Returns None if the ID doesn't correspond to an object in the list.
+    pub fn get_cloned(&self, id: AllocationID) -> Option<T> where T: Clone {
+        self.get(id).cloned()
+    }
 
-        let allocation = self.allocations.get_mut(self.next_allocation as usize);
+    /// Get a copy of the object with this ID. See `get_cloned`.
+    pub fn get_copied(&self, id: AllocationID) -> Option<T> where T: Copy {
+        self.get(id).copied()
+    }
 
-        match allocation {
-            None => { Err(AllocationError { allocation_index: self.next_allocation }) }
-            Some(allocation) => {
-                self.next_allocation = allocation.next_allocation;
-                allocation.allocation_id += 0x10000;
-                allocation.object_index = len as u16;
-                self.object_alloc_ids[len] = allocation.allocation_id;
-
-                Ok(allocation)
-            },
+    /// Get a mutable reference to the object with this ID, along with the ID as stored by the list.
+    /// Returns None if the ID doesn't correspond to an object in the list.
+    pub fn get_mut_with_id(&mut self, id: AllocationID) -> Option<(AllocationID, &mut T)> {
+        let index = self.index_of(id)?;
+        Some((self.object_alloc_ids[index], &mut self.objects[index]))
+    }
+
+    /// Apply a function to the object with this ID, returning the function's result.
+    /// Returns None, without calling the function, if the ID doesn't correspond to an object in the
+    /// list.
+    pub fn modify<R, F: FnOnce(&mut T) -> R>(&mut self, id: AllocationID, f: F) -> Option<R> {
+        self.get_mut(id).map(f)
+    }
+
+    /// Get mutable references to the objects of several IDs at once.
+    ///
+    /// Each ID gets its own result: None if the ID doesn't correspond to an object in the list, or if
+    /// an earlier ID in `ids` already refers to the same object, so that every returned reference is
+    /// to a distinct object.
+    pub fn get_each_mut<const N: usize>(&mut self, ids: [AllocationID; N]) -> [Option<&mut T>; N] {
+        let mut order: Vec<(usize, usize)> = ids.iter().enumerate()
+            .filter_map(|(k, &id)| self.index_of(id).map(|index| (index, k)))
+            .collect();
+        order.sort_unstable();
+        order.dedup_by_key(|&mut (index, _)| index);
+
+        let mut refs: [Option<&mut T>; N] = std::array::from_fn(|_| None);
+        let mut rest: &mut [T] = &mut self.objects;
+        let mut offset = 0;
+        for (index, k) in order {
+            let (_, tail) = std::mem::take(&mut rest).split_at_mut(index - offset);
+            let (object, tail) = tail.split_first_mut().unwrap();
+            refs[k] = Some(object);
+            rest = tail;
+            offset = index + 1;
         }
+        refs
     }
-}
 
-impl<T> Index<AllocationID> for PackedFreelist<T> {
-    type Output = T;
+    /// Get the object with this ID mutably, along with the objects before and after it in packed
+    /// order, so one object can be processed against all the others. The slices don't include the
+    /// object itself. Returns None if the ID doesn't correspond to an object in the list.
+    pub fn split_around_id(&mut self, id: AllocationID) -> Option<(&mut T, &mut [T], &mut [T])> {
+        let index = self.index_of(id)?;
+        let (before, rest) = self.objects.split_at_mut(index);
+        let (object, after) = rest.split_first_mut()?;
+        Some((object, before, after))
+    }
 
-    fn index(&self, index: AllocationID) -> &Self::Output {
-        let alloc = self.allocations.get((index & ALLOC_INDEX_MASK) as usize).unwrap();
-        self.objects.get(alloc.object_index as usize).unwrap()
+    /// Iterate over the objects in chunks of `size`, in packed order. The last chunk may be shorter.
+    ///
+    /// Objects are contiguous, so each chunk is a plain slice suited to batch or SIMD processing.
+    pub fn chunks(&self, size: usize) -> std::slice::Chunks<'_, T> {
+        self.objects.chunks(size)
+    }
+
+    /// Iterate mutably over the objects in chunks of `size`, in packed order. The last chunk may be
+    /// shorter.
+    pub fn chunks_mut(&mut self, size: usize) -> std::slice::ChunksMut<'_, T> {
+        self.objects.chunks_mut(size)
+    }
+
+    /// Iterate mutably over the objects in chunks of exactly `size`, in packed order. The objects
+    /// that don't fill a last chunk are available from the iterator's `into_remainder`.
+    pub fn chunks_exact_mut(&mut self, size: usize) -> std::slice::ChunksExactMut<'_, T> {
+        self.objects.chunks_exact_mut(size)
+    }
+
+    /// Iterate mutably over the objects as arrays of `N`, in packed order, such as for SIMD lanes.
+    /// The objects that don't fill a last array are skipped. Panics if `N` is 0.
+    pub fn array_chunks_mut<const N: usize>(&mut self) -> impl Iterator<Item = &mut [T; N]> {
+        self.objects.chunks_exact_mut(N).map(|chunk| chunk.try_into().unwrap())
+    }
+
+    /// Get the ID of the most recently inserted object, by any method of insertion.
+    /// Returns None if nothing was inserted yet, or if that object has since been removed.
+    pub fn last_inserted_id(&self) -> Option<AllocationID> {
+        self.last_inserted.filter(|&id| self.contains(id))
+    }
+
+    /// Count the objects that satisfy the predicate.
+    pub fn count_where<F: FnMut(&T) -> bool>(&self, mut pred: F) -> usize {
+        self.objects.iter().filter(|o| pred(o)).count()
+    }
+
+    /// Returns true if an object is equal to the value.
+    pub fn contains_value(&self, value: &T) -> bool where T: PartialEq {
+        self.objects.contains(value)
+    }
+
+    /// Returns true if any object satisfies the predicate. Stops at the first one that does.
+    pub fn any<F: FnMut(&T) -> bool>(&self, pred: F) -> bool {
+        self.objects.iter().any(pred)
+    }
+
+    /// Returns true if every object satisfies the predicate. Stops at the first one that doesn't.
+    /// An empty list trivially returns true.
+    pub fn all<F: FnMut(&T) -> bool>(&self, pred: F) -> bool {
+        self.objects.iter().all(pred)
+    }
+
+    /// Accumulate a value over every object, in packed order.
+    pub fn fold<B, F: FnMut(B, &T) -> B>(&self, init: B, f: F) -> B {
+        self.objects.iter().fold(init, f)
+    }
+
+    /// Rotate the objects in place so that the object at position `mid` becomes the first, like
+    /// `slice::rotate_left`. IDs are unaffected, only positions change.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid` is greater than `len`.
+    pub fn rotate_left(&mut self, mid: usize) {
+        self.objects.rotate_left(mid);
+        self.metadata.rotate_left(mid);
+        self.object_alloc_ids.rotate_left(mid);
+        self.reindex_from(0);
+        self.assert_valid();
+    }
+
+    /// Rotate the objects in place so that the last `k` objects become the first, like
+    /// `slice::rotate_right`. IDs are unaffected, only positions change.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is greater than `len`.
+    pub fn rotate_right(&mut self, k: usize) {
+        self.objects.rotate_right(k);
+        self.metadata.rotate_right(k);
+        self.object_alloc_ids.rotate_right(k);
+        self.reindex_from(0);
+        self.assert_valid();
+    }
+
+    /// Reorder the objects so that all those satisfying the predicate come first, returning the
+    /// number of them, which is the position of the first object that doesn't satisfy it.
+    ///
+    /// IDs are unaffected, only positions change. The relative order within each group isn't
+    /// preserved. The predicate is called exactly once per object.
+    pub fn partition_in_place<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> usize {
+        let mut front = 0;
+        let mut back = self.len();
+        loop {
+            while front < back && pred(&self.objects[front]) {
+                front += 1;
+            }
+            if front == back {
+                break;
+            }
+            // The object at `front` fails the predicate, so look for one after it that satisfies it.
+            back -= 1;
+            while front < back && !pred(&self.objects[back]) {
+                back -= 1;
+            }
+            if front == back {
+                break;
+            }
+            self.swap_positions(front, back);
+            front += 1;
+        }
+        self.assert_valid();
+        front
+    }
+
+    /// Insert an object, with default metadata.
+    pub fn insert(&mut self, value: T) -> Result<AllocationID, AllocationError> where M: Default {
+        self.insert_with_meta(value, M::default())
+    }
+
+    /// Insert an object along with its metadata.
+    pub fn insert_with_meta(&mut self, value: T, meta: M) -> Result<AllocationID, AllocationError> {
+        let allocation_id = self.insert_alloc()?.allocation_id;
+        self.objects.push(value);
+        self.metadata.push(meta);
+        self.assert_valid();
+        Ok(allocation_id)
+    }
+
+    /// Insert an object, returning its ID along with a mutable reference to it, for finishing its
+    /// initialization without looking it up again.
+    pub fn insert_mut(&mut self, value: T) -> Result<(AllocationID, &mut T), AllocationError> where M: Default {
+        let id = self.insert(value)?;
+        // New objects always go at the end of the packed objects.
+        Ok((id, self.objects.last_mut().unwrap()))
+    }
+
+    /// Insert an object at the end of the packed objects, returning its position rather than its ID,
+    /// for using the list as a stack.
+    ///
+    /// The object still gets an allocation, so it can be mixed with ID based operations; its ID is
+    /// available from `handle_at` or `last_inserted_id`. The position is only stable until the next
+    /// removal other than `pop`, since other removals move the last object into the gap.
+    pub fn push(&mut self, value: T) -> Result<usize, AllocationError> where M: Default {
+        self.insert(value)?;
+        Ok(self.len() - 1)
+    }
+
+    /// Remove the last object in packed order, returning it, or None if the list is empty.
+    /// No other object is moved.
+    pub fn pop(&mut self) -> Option<T> {
+        let &id = self.object_alloc_ids.last()?;
+        let (value, _) = self.remove_alloc((id & ALLOC_INDEX_MASK) as usize);
+        Some(value)
+    }
+
+    /// Insert an object, unless an equal object is already in the list.
+    /// Returns the ID of the new object, or of the existing one.
+    ///
+    /// This searches every object, so takes linear time. `intern::Interner` finds existing objects
+    /// in constant time for hashable types.
+    pub fn insert_unique(&mut self, value: T) -> Result<AllocationID, AllocationError> where T: PartialEq, M: Default {
+        match self.objects.iter().position(|object| *object == value) {
+            Some(index) => { Ok(self.object_alloc_ids[index]) },
+            None => { self.insert(value) },
+        }
+    }
+
+    /// Insert an object under a specific ID, such as one saved from another list, so that the ID
+    /// remains valid. The allocation of the ID must be free; if it's beyond the capacity, the list
+    /// grows to include it, regardless of the growth policy. Returns the value back if the
+    /// allocation is in use or beyond `MAX_SIZE`.
+    ///
+    /// The ID's generation must not be behind the allocation's current one, as compared by
+    /// `is_newer`, or the value is also returned back: otherwise, once the allocation is reused, it
+    /// would hand out IDs it already handed out, bringing stale handles of those back to life. An ID
+    /// of the allocation's current generation is accepted, reviving that ID.
+    ///
+    /// The allocation is taken out of the queue of free allocations wherever it is, which takes time
+    /// linear in the number of free allocations.
+    pub fn insert_with_id(&mut self, id: AllocationID, value: T) -> Result<(), T> where M: Default {
+        let alloc_index = (id & ALLOC_INDEX_MASK) as usize;
+        if alloc_index >= Self::MAX_SIZE {
+            return Err(value);
+        }
+        let current = match self.allocations.get(alloc_index) {
+            Some(allocation) => { allocation.allocation_id },
+            None => {
+                let generation = self.shrunk_generations.get(alloc_index - self.capacity()).cloned()
+                    .unwrap_or(self.generation_seed);
+                (AllocationID::from(generation) << GENERATION_SHIFT) | alloc_index as AllocationID
+            },
+        };
+        if is_newer(current, id) == Some(true) {
+            return Err(value);
+        }
+        if alloc_index >= self.capacity() {
+            self.grow_to(alloc_index + 1);
+        }
+        if self.allocations[alloc_index].object_index != TOMBSTONE {
+            return Err(value);
+        }
+
+        let next = self.allocations[alloc_index].next_allocation;
+        if self.next_allocation as usize == alloc_index {
+            self.next_allocation = next;
+        } else {
+            let free = self.free_queue();
+            let position = free.iter().position(|&index| index as usize == alloc_index)
+                .expect("a free allocation is in the free queue");
+            let previous = free[position - 1];
+            self.allocations[previous as usize].next_allocation = next;
+            if self.last_allocation as usize == alloc_index {
+                self.last_allocation = previous;
+            }
+        }
+
+        if let Some(dense) = &mut self.dense {
+            dense.assign(alloc_index);
+        }
+        let allocation = &mut self.allocations[alloc_index];
+        allocation.allocation_id = id;
+        allocation.object_index = self.objects.len() as u16;
+        self.object_alloc_ids.push(id);
+        self.objects.push(value);
+        self.metadata.push(M::default());
+        self.last_inserted = Some(id);
+        self.insertions += 1;
+        self.assert_valid();
+        Ok(())
+    }
+
+    /// Insert copies of every object in the slice, returning their IDs in the same order.
+    ///
+    /// The objects are copied into the packed storage in one go, which is faster than inserting them
+    /// one by one. Either all objects are inserted or, if there isn't enough capacity, none are.
+    pub fn extend_from_slice(&mut self, src: &[T]) -> Result<Vec<AllocationID>, AllocationError> where T: Copy, M: Default {
+        let ids = self.alloc_many(src.len())?;
+        self.objects.extend_from_slice(src);
+        self.metadata.extend(src.iter().map(|_| M::default()));
+        self.assert_valid();
+        Ok(ids)
+    }
+
+    /// Insert a block of objects at consecutive packed positions, returning the range of positions
+    /// they occupy. The ID of the object at each position is `handle_at` that position.
+    ///
+    /// The block occupies the returned positions straight after the call, so it can be uploaded or
+    /// processed as one range. The block only stays contiguous until the next removal: removing an
+    /// object swaps the last object into its place, which splits the block if the last object belongs
+    /// to it. Either all objects are inserted or, if there isn't enough capacity, none are. An empty
+    /// block inserts nothing, and its range is empty.
+    pub fn insert_block(&mut self, values: Vec<T>) -> Result<std::ops::Range<usize>, AllocationError> where M: Default {
+        let start = self.len();
+        self.alloc_many(values.len())?;
+        self.metadata.extend(values.iter().map(|_| M::default()));
+        self.objects.extend(values);
+        self.assert_valid();
+        Ok(start..self.len())
+    }
+
+    /// Insert `n` objects made by calling `f` with each object's offset into the block, returning
+    /// their IDs in order.
+    ///
+    /// The objects occupy consecutive packed positions, straight after the existing objects, like a
+    /// block from `insert_block`, but are constructed in place rather than collected first. Capacity
+    /// for all `n` is ensured up front, so either all objects are inserted or, if there isn't enough
+    /// capacity, `f` isn't called and none are. If `f` panics, the objects made so far are kept.
+    pub fn fill_n<F: FnMut(usize) -> T>(&mut self, n: usize, mut f: F) -> Result<Vec<AllocationID>, AllocationError> where M: Default {
+        self.ensure_capacity(n)?;
+
+        let mut ids = Vec::with_capacity(n);
+        for offset in 0..n {
+            let value = f(offset);
+            ids.push(self.insert(value)?);
+        }
+        Ok(ids)
+    }
+
+    /// Allocate IDs for `n` objects ahead of time, so they can be handed out before the objects
+    /// themselves are known. The objects are later given their values with `fill_reserved`.
+    ///
+    /// Until then, each reserved object holds `T::default()` and is indistinguishable from any other
+    /// live object: it is iterated over, indexable and counted by `len`. This keeps the packed
+    /// objects free of uninitialized gaps.
+    ///
+    /// Either all `n` IDs are reserved or, if there isn't enough capacity, none are.
+    pub fn reserve_ids(&mut self, n: usize) -> Result<Vec<AllocationID>, AllocationError> where T: Default, M: Default {
+        self.ensure_capacity(n)?;
+
+        let mut ids = Vec::with_capacity(n);
+        for _ in 0..n {
+            ids.push(self.insert(T::default())?);
+        }
+        Ok(ids)
+    }
+
+    /// Give a value to an object, typically one that was reserved by `reserve_ids`.
+    /// Returns the value back if the ID doesn't correspond to an object in the list.
+    pub fn fill_reserved(&mut self, id: AllocationID, value: T) -> Result<(), T> {
+        match self.index_of(id) {
+            None => { Err(value) },
+            Some(index) => {
+                self.objects[index] = value;
+                Ok(())
+            },
+        }
+    }
+
+    /// Remove an object, returning it.
+    /// Returns None if the ID doesn't correspond to an object in the list, such as an ID whose
+    /// object was already removed.
+    ///
+    /// The last object is moved into the removed object's place.
+    pub fn remove(&mut self, id: AllocationID) -> Option<T> {
+        self.index_of(id)?;
+        let (value, _) = self.remove_alloc((id & ALLOC_INDEX_MASK) as usize);
+        Some(value)
+    }
+
+    /// Remove an object if the ID corresponds to an object in the list, dropping it.
+    /// Returns whether an object was removed.
+    pub fn remove_if_present(&mut self, id: AllocationID) -> bool {
+        self.remove(id).is_some()
+    }
+
+    /// Keep only the objects satisfying the predicate, removing all others.
+    ///
+    /// The predicate is called exactly once per object, in packed order. The removals then move the
+    /// last objects into the gaps, like `remove`, so the packed order of the remaining objects isn't
+    /// kept. See `retain_stable` to keep it.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let positions = (0..self.len()).filter(|&index| !f(&self.objects[index])).collect();
+        self.remove_positions(positions);
+    }
+
+    /// Keep only the objects satisfying the predicate, removing all others, while keeping the
+    /// packed order of the remaining objects like `Vec::retain`.
+    ///
+    /// The predicate is called exactly once per object, in packed order. This takes a single pass,
+    /// but unlike removals that move the last object into each gap, every remaining object after the
+    /// first removed one is moved.
+    pub fn retain_stable<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut kept = 0;
+        for index in 0..self.len() {
+            if f(&self.objects[index]) {
+                if kept != index {
+                    self.swap_positions(kept, index);
+                }
+                kept += 1;
+            }
+        }
+
+        // The removed objects are now after the kept ones, so none of them need moving.
+        while self.len() > kept {
+            let id = self.object_alloc_ids[self.len() - 1];
+            self.remove_alloc((id & ALLOC_INDEX_MASK) as usize);
+        }
+    }
+
+    /// Remove the objects with these IDs, dropping them, and return how many were removed. IDs that
+    /// don't correspond to objects in the list, or that are repeated, are skipped.
+    ///
+    /// The objects are removed from the back of the packed objects to the front, so each removal
+    /// moves at most one remaining object, and never one that's about to be removed.
+    pub fn remove_many<I: IntoIterator<Item = AllocationID>>(&mut self, ids: I) -> usize {
+        let positions = ids.into_iter().filter_map(|id| self.index_of(id)).collect();
+        self.remove_positions(positions)
+    }
+
+    /// Keep only the objects whose IDs are in `keep`, removing all others.
+    pub fn retain_ids<I: IntoIterator<Item = AllocationID>>(&mut self, keep: I) {
+        let keep: std::collections::HashSet<AllocationID> = keep.into_iter().collect();
+        self.remove_where_id(|id| !keep.contains(&id));
+    }
+
+    /// Remove every object whose ID isn't in another list, such as a list of live entities that
+    /// this list holds components for.
+    ///
+    /// IDs are compared in full, so an object is removed if its allocation is in use in `live` but
+    /// by a different generation.
+    pub fn remove_ids_not_in<U, N>(&mut self, live: &PackedFreelist<U, N>) {
+        self.remove_where_id(|id| !live.contains(id));
+    }
+
+    /// Remove an object, reporting where the last object was moved to fill the gap.
+    ///
+    /// `on_move` is called with the moved object's previous position, its new position and its ID.
+    /// It isn't called if the removed object was the last one, since nothing needs to move.
+    /// Returns the removed object, or None if the ID doesn't correspond to an object in the list.
+    pub fn remove_tracked(&mut self, id: AllocationID, mut on_move: impl FnMut(usize, usize, AllocationID)) -> Option<T> {
+        let removal = self.remove_with_move(id)?;
+        if let Some((moved_id, index)) = removal.moved {
+            // The moved object was last before the removal.
+            on_move(self.len(), index, moved_id);
+        }
+        Some(removal.value)
+    }
+
+    /// Remove an object, returning it along with where the last object was moved to fill the gap.
+    /// Returns None if the ID doesn't correspond to an object in the list.
+    pub fn remove_with_move(&mut self, id: AllocationID) -> Option<Removal<T>> {
+        let index = self.index_of(id)?;
+        let (value, moved) = self.remove_alloc((id & ALLOC_INDEX_MASK) as usize);
+        Some(Removal { value, moved: moved.map(|moved_id| (moved_id, index)) })
+    }
+
+    /// Remove an object, returning it along with the ID of the object that was moved into its
+    /// position to fill the gap, if any. Returns `(None, None)` if the ID doesn't correspond to an
+    /// object in the list.
+    ///
+    /// The moved object was the last one; it's now where the removed object was. See
+    /// `remove_with_move` for the position as well.
+    pub fn remove_reporting_move(&mut self, id: AllocationID) -> (Option<T>, Option<AllocationID>) {
+        match self.remove_with_move(id) {
+            None => { (None, None) },
+            Some(removal) => { (Some(removal.value), removal.moved.map(|(moved_id, _)| moved_id)) },
+        }
+    }
+
+    /// Remove an object from this list and from every sibling list, returning it, or None if the ID
+    /// doesn't correspond to an object in this list, in which case no list is changed.
+    ///
+    /// Siblings are lists that hold the columns of the same rows, such as the components of the
+    /// entities in an archetype, so every insertion and removal is applied to all of them and they
+    /// hand out the same IDs. Each removal moves the same object into the gap, keeping the columns
+    /// aligned by position as well as by ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a sibling doesn't hold the object, or moves a different object into the gap, since
+    /// the lists are then out of step.
+    pub fn remove_synced(&mut self, id: AllocationID, others: &mut [&mut dyn AnyFreelist]) -> Option<T> {
+        let (value, moved) = self.remove_reporting_move(id);
+        let value = value?;
+        for (column, other) in others.iter_mut().enumerate() {
+            let (removed, other_moved) = other.remove_by_id(id);
+            assert!(removed, "PackedFreelist sibling {} is out of step: it doesn't hold ID {:#x}.", column, id);
+            assert!(other_moved == moved, "PackedFreelist sibling {} is out of step: removing ID {:#x} moved {:?} rather than {:?}.",
+                    column, id, other_moved, moved);
+        }
+        Some(value)
+    }
+
+    /// Remove an object, shifting every object after it down by one position like `Vec::remove`.
+    /// Returns the removed object, or None if the ID doesn't correspond to an object in the list.
+    ///
+    /// Unlike the other removals, which move the last object into the gap in constant time, this
+    /// preserves the packed order of the remaining objects. It takes time linear in the number of
+    /// objects after the removed one, since each of them has to be moved and its allocation updated.
+    pub fn shift_remove(&mut self, id: AllocationID) -> Option<T> {
+        let index = self.index_of(id)?;
+        let value = self.objects.remove(index);
+        self.metadata.remove(index);
+        self.object_alloc_ids.remove(index);
+        self.reindex_from(index);
+        self.free_ids(&[id]);
+        Some(value)
+    }
+
+    /// Remove objects from the front of the packed objects for as long as they satisfy the
+    /// predicate, returning them in packed order. Stops at the first object that doesn't satisfy it.
+    ///
+    /// The remaining objects are shifted down like `shift_remove`, keeping their packed order, so
+    /// the front stays meaningful across calls as long as objects are only ever removed this way or
+    /// with other order preserving removals. Any other removal moves the last object into the gap.
+    pub fn drain_while<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> Vec<T> {
+        let count = self.objects.iter().position(|object| !pred(object)).unwrap_or(self.len());
+        let drained: Vec<T> = self.objects.drain(..count).collect();
+        self.metadata.drain(..count);
+        let freed: Vec<AllocationID> = self.object_alloc_ids.drain(..count).collect();
+        self.reindex_from(0);
+        self.free_ids(&freed);
+        drained
+    }
+
+    /// Get the number of objects
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Returns true if the list holds no objects, whatever its capacity
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Get maximum number of elements
+    pub fn capacity(&self) -> usize {
+        self.allocations.len()
+    }
+
+    /// Point the allocations of the objects from this position onwards back at their objects, after
+    /// the objects moved.
+    fn reindex_from(&mut self, start: usize) {
+        self.reorders += 1;
+        for (index, &id) in self.object_alloc_ids.iter().enumerate().skip(start) {
+            self.allocations[(id & ALLOC_INDEX_MASK) as usize].object_index = index as u16;
+        }
+    }
+
+    /// Swap the objects at two positions, updating their allocations so their IDs stay valid.
+    fn swap_positions(&mut self, a: usize, b: usize) {
+        self.reorders += 1;
+        self.objects.swap(a, b);
+        self.metadata.swap(a, b);
+        self.object_alloc_ids.swap(a, b);
+        self.allocations[(self.object_alloc_ids[a] & ALLOC_INDEX_MASK) as usize].object_index = a as u16;
+        self.allocations[(self.object_alloc_ids[b] & ALLOC_INDEX_MASK) as usize].object_index = b as u16;
+    }
+
+    /// Remove every object whose ID satisfies the predicate.
+    fn remove_where_id<F: FnMut(AllocationID) -> bool>(&mut self, mut pred: F) {
+        let positions = (0..self.len()).filter(|&index| pred(self.object_alloc_ids[index])).collect();
+        self.remove_positions(positions);
+    }
+
+    /// Remove the objects at these positions, returning how many were removed. Positions may be
+    /// given in any order and more than once.
+    fn remove_positions(&mut self, mut positions: Vec<usize>) -> usize {
+        // Removing from the back means the last object, which fills each gap, is never one that's
+        // still to be removed: any later position was already removed.
+        positions.sort_unstable_by(|a, b| b.cmp(a));
+        positions.dedup();
+        for &index in &positions {
+            let id = self.object_alloc_ids[index];
+            self.remove_alloc((id & ALLOC_INDEX_MASK) as usize);
+        }
+        positions.len()
+    }
+
+    /// Free the allocations of every object, queueing them in packed order, and drop their metadata.
+    /// The objects themselves must be dropped or taken by the caller.
+    fn free_all(&mut self) {
+        self.metadata.clear();
+        let mut ids = std::mem::take(&mut self.object_alloc_ids);
+        self.free_ids(&ids);
+        ids.clear();
+        self.object_alloc_ids = ids;
+    }
+
+    /// Describe why an ID doesn't correspond to an object in the list, for panic messages.
+    fn invalid_id_message(&self, id: AllocationID) -> String {
+        let alloc_index = (id & ALLOC_INDEX_MASK) as usize;
+        let reason = match self.allocations.get(alloc_index) {
+            None => { "allocation index is out of range".to_string() },
+            Some(allocation) if allocation.object_index == TOMBSTONE => { "allocation is free".to_string() },
+            Some(allocation) if allocation.allocation_id != id => {
+                format!("allocation was reused, its current generation is {}", allocation.allocation_id >> GENERATION_SHIFT)
+            },
+            Some(_) => { "ID is valid".to_string() },
+        };
+        format!("Invalid AllocationID {:#x} (allocation {}, generation {}): {}. The list holds {} objects with capacity {}.",
+                id, alloc_index, id >> GENERATION_SHIFT, reason, self.len(), self.capacity())
+    }
+
+    /// Internal removal logic
+    /// Swaps the last object into the place of the one owned by this allocation, then frees the
+    /// allocation. Returns the removed object and the ID of the object that was moved, if any.
+    fn remove_alloc(&mut self, alloc_index: usize) -> (T, Option<AllocationID>) {
+        let object_index = self.allocations[alloc_index].object_index;
+        let last = self.objects.len() - 1;
+
+        let moved = if object_index as usize != last {
+            let moved_id = self.object_alloc_ids[last];
+            self.allocations[(moved_id & ALLOC_INDEX_MASK) as usize].object_index = object_index;
+            Some(moved_id)
+        } else {
+            None
+        };
+        let value = self.objects.swap_remove(object_index as usize);
+        self.metadata.swap_remove(object_index as usize);
+        let id = self.object_alloc_ids.swap_remove(object_index as usize);
+        self.free_ids(&[id]);
+
+        (value, moved)
+    }
+
+    /// Free the allocations of objects that were just taken out of the packed objects, along with
+    /// their IDs, queueing the allocations last in the order given.
+    fn free_ids(&mut self, ids: &[AllocationID]) {
+        // The objects are already gone, so the queue was empty if the list was full before.
+        let mut queue_empty = self.objects.len() + ids.len() == self.capacity();
+        self.removals += ids.len() as u64;
+
+        for &id in ids {
+            let alloc_index = (id & ALLOC_INDEX_MASK) as u16;
+            if queue_empty {
+                // The head of an empty queue is stale, so the freed allocation starts a new queue.
+                self.next_allocation = alloc_index;
+                queue_empty = false;
+            } else {
+                self.allocations[self.last_allocation as usize].next_allocation = alloc_index;
+            }
+            self.last_allocation = alloc_index;
+            self.allocations[alloc_index as usize].object_index = TOMBSTONE;
+            if let Some(dense) = &mut self.dense {
+                dense.release(alloc_index as usize);
+            }
+        }
+        self.assert_valid();
+    }
+
+    /// Give every object a new ID, so that every ID handed out so far stops corresponding to an
+    /// object, without removing any. The objects keep their packed order; get their new IDs with
+    /// `iter_with_ids` or `handle_at`.
+    ///
+    /// IDs have no bits to spare for a list-wide epoch, so each object's allocation moves on to its
+    /// next generation instead, as if the object were removed and inserted again. This takes time
+    /// linear in the number of objects, and brings each allocation a generation closer to reusing
+    /// IDs.
+    pub fn invalidate_all_handles(&mut self) {
+        for id in &mut self.object_alloc_ids {
+            let old_id = *id;
+            *id = next_generation(old_id);
+            self.allocations[(*id & ALLOC_INDEX_MASK) as usize].allocation_id = *id;
+            if self.last_inserted == Some(old_id) {
+                self.last_inserted = Some(*id);
+            }
+        }
+        self.assert_valid();
+    }
+
+    /// Remove every object, returning how many were removed. IDs of the removed objects become
+    /// invalid, as with any removal, and the capacity is kept.
+    pub fn remove_all(&mut self) -> usize {
+        let len = self.len();
+        self.objects.clear();
+        self.free_all();
+        len
+    }
+
+    /// Remove every object, like `remove_all`, for symmetry with `Vec::clear`. The capacity is kept,
+    /// and IDs handed out before the clear stay invalid when their allocations are reused.
+    pub fn clear(&mut self) {
+        self.remove_all();
+    }
+
+    /// Move objects off the allocations with the highest generations onto free allocations with
+    /// lower ones, so that no allocation nears reusing IDs long before the others. Returns a map from
+    /// the old ID of each moved object to its new ID; all other objects keep their IDs.
+    ///
+    /// Afterwards the objects are on the allocations with the lowest generations, and the free
+    /// allocations are queued from the lowest generation to the highest, so the most used ones are
+    /// reused last. The objects stay in packed order. Generations that already wrapped around look
+    /// low, so this only helps ahead of the first wraparound of any allocation. Takes O(n log n)
+    /// time in the capacity.
+    pub fn rebalance_generations(&mut self) -> std::collections::HashMap<AllocationID, AllocationID> {
+        let generation = |allocation: &Allocation| (allocation.allocation_id >> GENERATION_SHIFT) as u16;
+        // Among equal generations, objects stay where they are, so as few as possible move.
+        let mut by_generation: Vec<usize> = (0..self.capacity()).collect();
+        by_generation.sort_by_key(|&index| {
+            let allocation = &self.allocations[index];
+            (generation(allocation), allocation.object_index == TOMBSTONE)
+        });
+        let (targets, spares) = by_generation.split_at(self.len());
+        let free_targets: Vec<usize> = targets.iter().cloned()
+            .filter(|&index| self.allocations[index].object_index == TOMBSTONE)
+            .collect();
+        let mut destinations = free_targets.into_iter();
+
+        let mut remap = std::collections::HashMap::new();
+        self.reorders += 1;
+        for &index in spares {
+            let object_index = self.allocations[index].object_index;
+            if object_index == TOMBSTONE {
+                continue;
+            }
+            let destination = destinations.next().expect("there are as many free targets as objects on spares");
+            let old_id = self.allocations[index].allocation_id;
+            let new_id = next_generation(self.allocations[destination].allocation_id);
+            self.allocations[destination].allocation_id = new_id;
+            self.allocations[destination].object_index = object_index;
+            self.allocations[index].object_index = TOMBSTONE;
+            self.object_alloc_ids[object_index as usize] = new_id;
+            if let Some(dense) = &mut self.dense {
+                dense.moved(index, destination);
+            }
+            if self.last_inserted == Some(old_id) {
+                self.last_inserted = Some(new_id);
+            }
+            remap.insert(old_id, new_id);
+        }
+
+        let free: Vec<u16> = by_generation.iter()
+            .filter(|&&index| self.allocations[index].object_index == TOMBSTONE)
+            .map(|&index| index as u16)
+            .collect();
+        self.link_free_queue(&free);
+        self.assert_valid();
+        remap
+    }
+
+    /// Remove every object, then insert the objects from the iterator, returning their IDs in order.
+    /// IDs of the removed objects become invalid, as with any removal.
+    ///
+    /// The storage of the list is reused, so refilling with no more objects than the capacity never
+    /// reallocates it, which suits rebuilding a list every frame. The objects are collected before
+    /// any are inserted, and the IDs returned in a new vector, so each call still allocates those.
+    ///
+    /// If the list can't hold every object, even by growing, it's left untouched and an error
+    /// returned, so no objects are inserted without their IDs being returned.
+    pub fn refill<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<Vec<AllocationID>, AllocationError> where M: Default {
+        let values: Vec<T> = iter.into_iter().collect();
+        self.ensure_capacity(values.len().saturating_sub(self.len()))?;
+        self.remove_all();
+        Ok(values.into_iter().map(|value| self.insert(value).expect("the list has room for every object")).collect())
+    }
+
+    /// Remove every object and reset every allocation to its initial generation, so the list hands
+    /// out the same IDs as a freshly constructed list of the same capacity and generation seed.
+    /// The capacity, growth policy and grow callback are kept.
+    ///
+    /// This is meant for reproducible runs, such as replay tests that compare IDs against recorded
+    /// ones. IDs handed out before the reset are *not* invalidated: once the list reuses their
+    /// allocations they validate against the new objects, so no old ID may be used after a reset.
+    /// This includes IDs of allocations dropped by shrinking, which restart from the generation seed
+    /// if the list grows back.
+    pub fn reset(&mut self) {
+        self.removals += self.len() as u64;
+        self.objects.clear();
+        self.metadata.clear();
+        self.object_alloc_ids.clear();
+        self.last_inserted = None;
+        self.shrunk_generations.clear();
+        if let Some(dense) = &mut self.dense {
+            *dense = DenseIndices::default();
+        }
+
+        let capacity = self.capacity();
+        let seed = AllocationID::from(self.generation_seed) << GENERATION_SHIFT;
+        for (index, allocation) in self.allocations.iter_mut().enumerate() {
+            allocation.allocation_id = seed | index as AllocationID;
+            allocation.object_index = TOMBSTONE;
+            allocation.next_allocation = ((index + 1) % capacity) as u16;
+        }
+        self.next_allocation = 0;
+        self.last_allocation = capacity.saturating_sub(1) as u16;
+        self.assert_valid();
+    }
+
+    /// Get the number of objects ever inserted and removed, along with the current size.
+    ///
+    /// The counts are kept for the lifetime of the list, including across `reset`, so they show the
+    /// churn of a long lived pool. Dividing `total_inserts` by the capacity gives the average number
+    /// of times each allocation was used, a rough measure of how close IDs are to being reused.
+    pub fn stats(&self) -> Stats {
+        Stats {
+            total_inserts: self.insertions,
+            total_removes: self.removals + self.rolled_back,
+            len: self.len(),
+            capacity: self.capacity(),
+        }
+    }
+
+    /// Save the current state so that the insertions that follow can be undone with `rollback`.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint { len: self.len(), removals: self.removals, reorders: self.reorders }
+    }
+
+    /// Undo every insertion made since the checkpoint was taken, dropping the inserted objects.
+    /// IDs of the dropped objects become invalid, while all other IDs remain valid.
+    ///
+    /// Only sequences of insertions (and capacity growth) can be rolled back. If any object was
+    /// removed or moved, such as by `rotate_left` or `partition_in_place`, or the capacity shrunk
+    /// since the checkpoint was taken, the list is left untouched and an error returned.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) -> Result<(), RollbackError> {
+        let len = self.len();
+        if len < checkpoint.len || self.removals != checkpoint.removals || self.reorders != checkpoint.reorders {
+            return Err(RollbackError { checkpoint });
+        }
+
+        // Insertions take allocations from the front of the queue without relinking them, so the
+        // allocations taken since the checkpoint are still chained in order ahead of the queue.
+        let taken: Vec<u16> = self.object_alloc_ids[checkpoint.len..len].iter()
+            .map(|&id| (id & ALLOC_INDEX_MASK) as u16)
+            .collect();
+        let (first, last) = match (taken.first(), taken.last()) {
+            (Some(&first), Some(&last)) => { (first, last) },
+            _ => { return Ok(()) },
+        };
+        let queue_empty = len == self.capacity();
+        let linked = taken.windows(2).all(|pair| self.allocations[pair[0] as usize].next_allocation == pair[1])
+            && (queue_empty || self.allocations[last as usize].next_allocation == self.next_allocation);
+        if !linked {
+            return Err(RollbackError { checkpoint });
+        }
+
+        self.rolled_back += taken.len() as u64;
+        self.objects.truncate(checkpoint.len);
+        self.metadata.truncate(checkpoint.len);
+        self.object_alloc_ids.truncate(checkpoint.len);
+        for &index in &taken {
+            self.allocations[index as usize].object_index = TOMBSTONE;
+            if let Some(dense) = &mut self.dense {
+                dense.release(index as usize);
+            }
+        }
+        if queue_empty {
+            self.last_allocation = last;
+        }
+        self.next_allocation = first;
+        self.assert_valid();
+
+        Ok(())
+    }
+
+    /// Iterate over the objects mutably, in packed order. Objects are iterated immutably through
+    /// `Deref`.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.objects.iter_mut()
+    }
+
+    /// Iterate over the objects along with their IDs, in packed order.
+    ///
+    /// Iterating in reverse visits objects from the last position to the first, so removing each
+    /// visited object never moves an unvisited object into an already visited position.
+    pub fn iter_with_ids(&self) -> IterWithIds<'_, T> {
+        IterWithIds::new(self.iter_indexed())
+    }
+
+    /// Iterate over the objects mutably along with their IDs, in packed order. The IDs can't be
+    /// changed, only the objects.
+    pub fn iter_with_ids_mut(&mut self) -> IterWithIdsMut<'_, T> {
+        IterWithIdsMut::new(&self.object_alloc_ids, &mut self.objects)
+    }
+
+    /// Iterate over the objects along with their positions and IDs, in packed order.
+    /// Each position is what `index_of` returns for the ID, as long as the list isn't changed.
+    pub fn iter_indexed(&self) -> IterIndexed<'_, T> {
+        IterIndexed::new(&self.object_alloc_ids, &self.objects)
+    }
+
+    /// Iterate over the objects along with their positions and IDs, in packed order.
+    /// This is the same as `iter_indexed`.
+    pub fn iter_full(&self) -> impl ExactSizeIterator<Item = (usize, AllocationID, &T)> + '_ {
+        self.iter_indexed()
+    }
+
+    /// Iterate over the IDs of the objects satisfying the predicate, in packed order.
+    ///
+    /// This only borrows the list, so collect the IDs before acting on them, such as removing them.
+    pub fn filter_ids<'a, F: FnMut(&T) -> bool + 'a>(&'a self, mut pred: F) -> impl Iterator<Item = AllocationID> + 'a {
+        self.iter_with_ids().filter(move |(_, object)| pred(object)).map(|(id, _)| id)
+    }
+
+    /// Iterate over the objects as entries, which give access to each object and its ID, in packed
+    /// order.
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = entry::Entry<'_, T>> + '_ {
+        self.iter_with_ids().map(|(id, value)| entry::Entry::new(id, value))
+    }
+
+    /// Iterate over the objects as entries that can modify the objects, in packed order.
+    pub fn entries_mut(&mut self) -> impl ExactSizeIterator<Item = entry::EntryMut<'_, T>> + '_ {
+        self.iter_with_ids_mut().map(|(id, value)| entry::EntryMut::new(id, value))
+    }
+
+    /// Call a function with the ID of every object and a mutable reference to it, in packed order.
+    #[inline]
+    pub fn for_each_mut<F: FnMut(AllocationID, &mut T)>(&mut self, mut f: F) {
+        for (id, object) in self.iter_with_ids_mut() {
+            f(id, object);
+        }
+    }
+
+    /// Iterate over the objects of this list that have an object with the same ID in the other list,
+    /// yielding the ID along with mutable references to both objects, in this list's packed order.
+    /// Objects with no counterpart in the other list are skipped.
+    ///
+    /// This is the joint iteration of a system over two components of the same entities. The
+    /// positions of the counterparts are looked up before iterating, which takes time and memory
+    /// linear in the number of objects in both lists.
+    pub fn zip_mut<'a, U, N>(&'a mut self, other: &'a mut PackedFreelist<U, N>) -> impl Iterator<Item = (AllocationID, &'a mut T, &'a mut U)> + 'a {
+        let positions: Vec<Option<usize>> = self.object_alloc_ids.iter().map(|&id| other.index_of(id)).collect();
+        // Each ID is in this list once, so each counterpart is taken at most once.
+        let mut counterparts: Vec<Option<&'a mut U>> = other.objects.iter_mut().map(Some).collect();
+        self.iter_with_ids_mut().zip(positions)
+            .filter_map(move |((id, object), position)| {
+                let counterpart = counterparts[position?].take()?;
+                Some((id, object, counterpart))
+            })
+    }
+
+    /// Remove every object, yielding them in the order given by the comparison function instead of
+    /// packed order. The sort is stable.
+    ///
+    /// The list is emptied as soon as this is called, so it's empty even if the iterator is dropped
+    /// before it's exhausted. IDs of the removed objects become invalid.
+    pub fn drain_sorted_by<F: FnMut(&T, &T) -> std::cmp::Ordering>(&mut self, cmp: F) -> std::vec::IntoIter<T> {
+        let capacity = self.capacity();
+        let mut objects = std::mem::replace(&mut self.objects, Vec::with_capacity(capacity));
+        self.free_all();
+        objects.sort_by(cmp);
+        objects.into_iter()
+    }
+
+    /// Remove every object, yielding them in ascending order. See `drain_sorted_by`.
+    pub fn drain_sorted(&mut self) -> std::vec::IntoIter<T> where T: Ord {
+        self.drain_sorted_by(Ord::cmp)
+    }
+
+    /// Iterate over the allocation indices that are in use, one per object, in packed order.
+    ///
+    /// This reads the IDs stored alongside the objects, so it takes time linear in the number of
+    /// objects rather than in the capacity, and never visits free allocations. Scanning a bitset of
+    /// live allocations would instead take time linear in the capacity.
+    pub fn live_slot_indices(&self) -> impl ExactSizeIterator<Item = usize> + '_ {
+        self.object_alloc_ids.iter().map(|&id| (id & ALLOC_INDEX_MASK) as usize)
+    }
+
+    /// Iterate over the allocations in order of their index, describing the state of each, for
+    /// tracking down bugs in the bookkeeping such as a broken queue of free allocations.
+    ///
+    /// The queue starts at `next_free_slot` and follows the `next` of each free allocation.
+    pub fn debug_slots(&self) -> impl ExactSizeIterator<Item = SlotInfo> + '_ {
+        self.allocations.iter().map(|allocation| {
+            if allocation.object_index == TOMBSTONE {
+                SlotInfo::Free { id: allocation.allocation_id, next: allocation.next_allocation as usize }
+            } else {
+                SlotInfo::Live { id: allocation.allocation_id, object_index: allocation.object_index as usize }
+            }
+        })
+    }
+
+    /// Start assigning each object a dense index: a small integer that stays the same for the
+    /// object's whole lifetime, unlike its packed position, so it can index fixed external arrays.
+    /// Objects already in the list are assigned dense indices in packed order. Does nothing if
+    /// dense indices are already enabled.
+    ///
+    /// Removed objects' dense indices are reused, most recently released first, so dense indices
+    /// stay below the largest number of objects the list has held at once since enabling them. This
+    /// costs a little extra work on every insertion and removal, and two bytes per allocation.
+    pub fn enable_dense_indices(&mut self) {
+        if self.dense.is_some() {
+            return;
+        }
+        let mut dense = DenseIndices::default();
+        for &id in &self.object_alloc_ids {
+            dense.assign((id & ALLOC_INDEX_MASK) as usize);
+        }
+        self.dense = Some(dense);
+    }
+
+    /// Get the dense index of an object. Returns None if the ID doesn't correspond to an object in
+    /// the list, or if dense indices aren't enabled. See `enable_dense_indices`.
+    pub fn dense_index(&self, id: AllocationID) -> Option<usize> {
+        let dense = self.dense.as_ref()?;
+        if !self.contains(id) {
+            return None;
+        }
+        Some(dense.of_allocation[(id & ALLOC_INDEX_MASK) as usize] as usize)
+    }
+
+    /// Get the index of the allocation the next insertion will use, or None if the list is full.
+    pub fn next_free_slot(&self) -> Option<usize> {
+        if self.len() < self.capacity() { Some(self.next_allocation as usize) } else { None }
+    }
+
+    /// Iterate over the objects along with their IDs, ordered by the allocation index of their ID.
+    ///
+    /// Unlike packed order, this order doesn't depend on the history of removals, so it's suited to
+    /// reproducible output. Sorting makes this O(n log n), versus O(n) for the packed iterators.
+    pub fn iter_by_id(&self) -> impl ExactSizeIterator<Item = (AllocationID, &T)> + '_ {
+        let mut ids: Vec<(AllocationID, usize)> = self.object_alloc_ids.iter().cloned().zip(0..).collect();
+        ids.sort_unstable_by_key(|&(id, _)| id & ALLOC_INDEX_MASK);
+        ids.into_iter().map(move |(id, index)| (id, &self.objects[index]))
+    }
+
+    /// Iterate over the objects whose IDs fall within `range`, along with their IDs, in packed order.
+    ///
+    /// IDs aren't stored sorted, so this scans every object, taking O(n) time however few match.
+    /// IDs compare by generation before allocation index, so a range spanning generations, such as
+    /// the IDs of a list seeded with `with_capacity_seeded`, selects by generation. Collect and sort
+    /// the results for a stable order.
+    pub fn iter_id_range(&self, range: std::ops::Range<AllocationID>) -> impl Iterator<Item = (AllocationID, &T)> + '_ {
+        self.iter_with_ids().filter(move |(id, _)| range.contains(id))
+    }
+
+    /// Clone the objects along with their IDs, ordered by the allocation index of their ID like
+    /// `iter_by_id`.
+    ///
+    /// The order doesn't depend on the history of removals, so snapshots of lists holding the same
+    /// objects under the same IDs are equal, which makes them suited to diffing. Sorting makes this
+    /// O(n log n), versus O(n) for collecting `iter_with_ids` in packed order.
+    pub fn snapshot(&self) -> Vec<(AllocationID, T)> where T: Clone {
+        self.iter_by_id().map(|(id, object)| (id, object.clone())).collect()
+    }
+
+    /// Consume the list, yielding each object along with its ID, in packed order.
+    pub fn into_iter_with_ids(self) -> impl ExactSizeIterator<Item = (AllocationID, T)> {
+        self.object_alloc_ids.into_iter().zip(self.objects)
+    }
+
+    /// Consume the list, returning the objects sorted by the comparison function. The sort is stable.
+    pub fn into_sorted_vec_by<F: FnMut(&T, &T) -> std::cmp::Ordering>(mut self, cmp: F) -> Vec<T> {
+        self.objects.sort_by(cmp);
+        self.objects
+    }
+
+    /// Consume the list, returning the objects in ascending order.
+    pub fn into_sorted_vec(self) -> Vec<T> where T: Ord {
+        self.into_sorted_vec_by(Ord::cmp)
+    }
+
+    /// Consume the list, returning just the objects in packed order, without any spare capacity.
+    pub fn into_boxed_slice(self) -> Box<[T]> {
+        self.objects.into_boxed_slice()
+    }
+
+    /// Consume the list, returning the objects along with their IDs in packed order, without any
+    /// spare capacity.
+    pub fn into_id_boxed_slice(self) -> Box<[(AllocationID, T)]> {
+        self.into_iter_with_ids().collect()
+    }
+
+    /// Ensure there is room to allocate `additional` more objects, growing according to the growth
+    /// policy if needed.
+    fn ensure_capacity(&mut self, additional: usize) -> Result<(), AllocationError> {
+        // Any count that overflows is beyond `MAX_SIZE` anyway.
+        let required = match self.len().checked_add(additional) {
+            Some(required) => { required },
+            None => { return Err(self.allocation_error(AllocationErrorKind::AtCapacity)) },
+        };
+        let mut capacity = self.capacity();
+        while capacity < required {
+            let grown = std::cmp::min(self.growth_policy.grow(capacity), Self::MAX_SIZE);
+            if grown <= capacity {
+                return Err(self.allocation_error(AllocationErrorKind::AtCapacity));
+            }
+            capacity = grown;
+        }
+
+        if capacity > self.capacity() {
+            self.grow_to(capacity);
+        }
+        Ok(())
+    }
+
+    fn allocation_error(&self, kind: AllocationErrorKind) -> AllocationError {
+        AllocationError { kind, len: self.len(), capacity: self.capacity() }
+    }
+
+    /// Add free allocations until there are `capacity` of them.
+    /// The new allocations are queued after the existing free allocations.
+    fn grow_to(&mut self, capacity: usize) {
+        let old_capacity = self.capacity();
+        debug_assert!(old_capacity < capacity && capacity <= Self::MAX_SIZE);
+
+        self.objects.reserve_exact(capacity - self.objects.len());
+        self.metadata.reserve_exact(capacity - self.metadata.len());
+        self.object_alloc_ids.reserve_exact(capacity - self.object_alloc_ids.len());
+        let restored = std::cmp::min(capacity - old_capacity, self.shrunk_generations.len());
+        let generations = self.shrunk_generations.drain(..restored)
+            .chain(std::iter::repeat(self.generation_seed));
+        self.allocations.extend((old_capacity as u16..capacity as u16).zip(generations).map(|(i, generation)| Allocation {
+            allocation_id: (AllocationID::from(generation) << GENERATION_SHIFT) | AllocationID::from(i),
+            object_index: TOMBSTONE,
+            next_allocation: i + 1
+        }));
+
+        // The tail is linked even when the queue was empty, so the allocations taken since a
+        // checkpoint stay chained ahead of the new ones and can still be rolled back.
+        if old_capacity > 0 {
+            self.allocations[self.last_allocation as usize].next_allocation = old_capacity as u16;
+        }
+        if self.len() == old_capacity {
+            // No allocations were free, so the queue restarts at the new ones.
+            self.next_allocation = old_capacity as u16;
+        }
+        self.last_allocation = (capacity - 1) as u16;
+
+        if let Some(on_grow) = &mut self.on_grow.0 {
+            on_grow(old_capacity, capacity);
+        }
+    }
+
+    /// The indices of the free allocations, in the order they will be allocated.
+    fn free_queue(&self) -> Vec<u16> {
+        let mut free = Vec::with_capacity(self.capacity() - self.len());
+        let mut index = self.next_allocation;
+        for _ in 0..free.capacity() {
+            free.push(index);
+            index = self.allocations[index as usize].next_allocation;
+        }
+        free
+    }
+
+    /// Chain these free allocations together, in order, as the queue of allocations to allocate.
+    fn link_free_queue(&mut self, free: &[u16]) {
+        for pair in free.windows(2) {
+            self.allocations[pair[0] as usize].next_allocation = pair[1];
+        }
+        self.next_allocation = free.first().cloned().unwrap_or(0);
+        self.last_allocation = free.last().cloned().unwrap_or(0);
+    }
+
+    /// Allocate `n` objects, in order, after the existing objects.
+    /// The caller must then push the objects themselves.
+    fn alloc_many(&mut self, n: usize) -> Result<Vec<AllocationID>, AllocationError> {
+        self.ensure_capacity(n)?;
+
+        let mut ids = Vec::with_capacity(n);
+        for _ in 0..n {
+            ids.push(self.insert_alloc()?.allocation_id);
+        }
+        Ok(ids)
+    }
+
+    /// Internal allocation logic
+    fn insert_alloc(&mut self) -> Result<&Allocation, AllocationError> {
+        self.ensure_capacity(1)?;
+        // Object IDs are pushed here, ahead of the objects, so they mark the next object's position.
+        let len = self.object_alloc_ids.len();
+
+        if self.next_allocation as usize >= self.allocations.len() {
+            return Err(self.allocation_error(AllocationErrorKind::CorruptFreeList));
+        }
+
+        if let Some(dense) = &mut self.dense {
+            dense.assign(self.next_allocation as usize);
+        }
+        let allocation = &mut self.allocations[self.next_allocation as usize];
+        self.next_allocation = allocation.next_allocation;
+        allocation.allocation_id = next_generation(allocation.allocation_id);
+        allocation.object_index = len as u16;
+        self.object_alloc_ids.push(allocation.allocation_id);
+        self.last_inserted = Some(allocation.allocation_id);
+        self.insertions += 1;
+
+        Ok(allocation)
+    }
+
+    /// The largest capacity that `assert_valid` checks. Checking takes time linear in the capacity,
+    /// so larger lists are left unchecked to keep every mutation of a debug build cheap.
+    #[cfg(debug_assertions)]
+    const ASSERT_VALID_MAX_CAPACITY: usize = 1024;
+
+    /// Check the bookkeeping of the list, called at the end of every mutation. Panics with a report
+    /// of the first inconsistency found. In release builds this compiles away entirely.
+    #[inline]
+    fn assert_valid(&self) {
+        #[cfg(debug_assertions)]
+        {
+            if self.capacity() <= Self::ASSERT_VALID_MAX_CAPACITY {
+                if let Err(report) = self.check_invariants() {
+                    panic!("PackedFreelist is corrupt: {}. The list holds {} objects with capacity {}.",
+                           report, self.len(), self.capacity());
+                }
+            }
+        }
+    }
+
+    /// Describe the first inconsistency between the objects, their IDs, the allocations and the
+    /// free queue, if any.
+    #[cfg(any(debug_assertions, feature = "binary"))]
+    fn check_invariants(&self) -> Result<(), String> {
+        let len = self.len();
+        let capacity = self.capacity();
+        if self.object_alloc_ids.len() != len {
+            return Err(format!("there are {} objects but {} object IDs", len, self.object_alloc_ids.len()));
+        }
+        if self.metadata.len() != len {
+            return Err(format!("there are {} objects but {} metadata", len, self.metadata.len()));
+        }
+        if len > capacity {
+            return Err("there are more objects than allocations".to_string());
+        }
+
+        // Every object's ID must lead back to the object...
+        for (index, &id) in self.object_alloc_ids.iter().enumerate() {
+            let alloc_index = (id & ALLOC_INDEX_MASK) as usize;
+            match self.allocations.get(alloc_index) {
+                None => {
+                    return Err(format!("object {} has ID {:#x}, whose allocation {} is out of range", index, id, alloc_index));
+                },
+                Some(allocation) if allocation.allocation_id != id => {
+                    return Err(format!("object {} has ID {:#x}, but allocation {} has ID {:#x}",
+                                       index, id, alloc_index, allocation.allocation_id));
+                },
+                Some(allocation) if allocation.object_index as usize != index => {
+                    return Err(format!("object {} has ID {:#x}, but allocation {} points to object {}",
+                                       index, id, alloc_index, allocation.object_index));
+                },
+                Some(_) => {},
+            }
+        }
+
+        // ...and every allocation in use must lead back to its object, so the rest must be free.
+        for (alloc_index, allocation) in self.allocations.iter().enumerate() {
+            if (allocation.allocation_id & ALLOC_INDEX_MASK) as usize != alloc_index {
+                return Err(format!("allocation {} has ID {:#x}, which is for another allocation", alloc_index, allocation.allocation_id));
+            }
+            if allocation.object_index == TOMBSTONE {
+                continue;
+            }
+            let object_id = self.object_alloc_ids.get(allocation.object_index as usize);
+            if object_id != Some(&allocation.allocation_id) {
+                return Err(format!("allocation {} with ID {:#x} points to object {}, which has ID {:?}",
+                                   alloc_index, allocation.allocation_id, allocation.object_index, object_id));
+            }
+        }
+
+        // The free queue must visit every free allocation exactly once, ending at its tail.
+        let free = capacity - len;
+        let mut queued = vec![false; capacity];
+        let mut alloc_index = self.next_allocation as usize;
+        for step in 0..free {
+            let allocation = match self.allocations.get(alloc_index) {
+                None => { return Err(format!("free queue step {} reaches allocation {}, which is out of range", step, alloc_index)) },
+                Some(allocation) => { allocation },
+            };
+            if allocation.object_index != TOMBSTONE {
+                return Err(format!("free queue step {} reaches allocation {}, which is in use by object {}",
+                                   step, alloc_index, allocation.object_index));
+            }
+            if queued[alloc_index] {
+                return Err(format!("free queue step {} revisits allocation {}", step, alloc_index));
+            }
+            queued[alloc_index] = true;
+            if step == free - 1 && alloc_index != self.last_allocation as usize {
+                return Err(format!("free queue ends at allocation {}, but its tail is allocation {}",
+                                   alloc_index, self.last_allocation));
+            }
+            alloc_index = allocation.next_allocation as usize;
+        }
+
+        Ok(())
+    }
+}
+
+/// Borrow the objects with the same ID in two lists mutably at once, such as two components of an
+/// entity. Returns None unless both lists hold an object with this ID.
+pub fn get_pair_mut<'a, A, MA, B, MB>(a: &'a mut PackedFreelist<A, MA>, b: &'a mut PackedFreelist<B, MB>, id: AllocationID) -> Option<(&'a mut A, &'a mut B)> {
+    let index_a = a.index_of(id)?;
+    let index_b = b.index_of(id)?;
+    Some((&mut a.objects[index_a], &mut b.objects[index_b]))
+}
+
+/// A `PackedFreelist` of any type of object, for applying removals across sibling lists.
+/// See `PackedFreelist::remove_synced`.
+pub trait AnyFreelist {
+    /// Remove the object with this ID, dropping it. Returns whether there was such an object, along
+    /// with the ID of the object that was moved into its position to fill the gap, if any.
+    fn remove_by_id(&mut self, id: AllocationID) -> (bool, Option<AllocationID>);
+}
+
+impl<T, M> AnyFreelist for PackedFreelist<T, M> {
+    fn remove_by_id(&mut self, id: AllocationID) -> (bool, Option<AllocationID>) {
+        let (value, moved) = self.remove_reporting_move(id);
+        (value.is_some(), moved)
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<T: bytemuck::Pod> PackedFreelist<T> {
+    /// View the live objects as raw bytes, in packed order.
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.objects)
+    }
+
+    /// Construct a full freelist from the bytes of packed objects, such as those produced by `as_bytes`.
+    ///
+    /// Objects are given sequential IDs in the order they appear and the resulting freelist has no
+    /// free capacity. The bytes don't need to be aligned for `T`.
+    // `is_multiple_of` needs Rust 1.87, so the modulo keeps older toolchains building.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, bytemuck::PodCastError> {
+        let size = std::mem::size_of::<T>();
+        if size == 0 {
+            return Err(bytemuck::PodCastError::SizeMismatch);
+        }
+        if bytes.len() % size != 0 {
+            return Err(bytemuck::PodCastError::OutputSliceWouldHaveSlop);
+        }
+
+        Ok(Self::from_vec(bytes.chunks_exact(size).map(bytemuck::pod_read_unaligned).collect()))
+    }
+}
+
+/// Shows the counts reported by `stats`, then the objects by ID, in packed order. The bookkeeping
+/// behind the IDs is left out; see `debug_slots` for it.
+impl<T: std::fmt::Debug, M> std::fmt::Debug for PackedFreelist<T, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let stats = self.stats();
+        f.debug_struct("PackedFreelist")
+            .field("len", &stats.len)
+            .field("capacity", &stats.capacity)
+            .field("total_inserts", &stats.total_inserts)
+            .field("total_removes", &stats.total_removes)
+            .field("objects", &DebugObjects(self))
+            .finish()
+    }
+}
+
+/// Formats the objects of a list as a map from ID, in hex, to object.
+struct DebugObjects<'a, T, M>(&'a PackedFreelist<T, M>);
+
+impl<'a, T: std::fmt::Debug, M> std::fmt::Debug for DebugObjects<'a, T, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.0.iter_with_ids().map(|(id, object)| (DebugId(id), object))).finish()
+    }
+}
+
+struct DebugId(AllocationID);
+
+impl std::fmt::Debug for DebugId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+/// Compares the objects in packed order.
+///
+/// Packed order depends on the history of operations: every removal moves the last object into
+/// the removed object's position. Only compare against a specific order when it is known.
+impl<T: PartialEq, M> PartialEq<[T]> for PackedFreelist<T, M> {
+    fn eq(&self, other: &[T]) -> bool {
+        self.objects[..] == other[..]
+    }
+}
+
+/// Compares the objects in packed order. See the comparison with `[T]`.
+impl<T: PartialEq, M> PartialEq<Vec<T>> for PackedFreelist<T, M> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        self.objects[..] == other[..]
+    }
+}
+
+/// Compares the objects in packed order. See the comparison with `[T]`.
+impl<T: PartialEq, M, const N: usize> PartialEq<[T; N]> for PackedFreelist<T, M> {
+    fn eq(&self, other: &[T; N]) -> bool {
+        self.objects[..] == other[..]
+    }
+}
+
+/// Indexes by ID: `list[id]` is the object with this ID, wherever it's packed.
+/// Panics if the ID doesn't correspond to an object in the list, including stale IDs whose
+/// allocation has since been reused, rather than returning the object that reused it.
+impl<T, M> Index<AllocationID> for PackedFreelist<T, M> {
+    type Output = T;
+
+    fn index(&self, index: AllocationID) -> &Self::Output {
+        match self.index_of(index) {
+            Some(object_index) => { &self.objects[object_index] },
+            None => { panic!("{}", self.invalid_id_message(index)) },
+        }
+    }
+}
+
+/// Mutably indexes by ID, resolving the ID like `Index`: `list[id] = value` replaces the object with
+/// this ID in place, keeping its ID.
+/// Panics if the ID doesn't correspond to an object in the list, including stale IDs.
+impl<T, M> IndexMut<AllocationID> for PackedFreelist<T, M> {
+    fn index_mut(&mut self, index: AllocationID) -> &mut Self::Output {
+        match self.index_of(index) {
+            Some(object_index) => { &mut self.objects[object_index] },
+            None => { panic!("{}", self.invalid_id_message(index)) },
+        }
+    }
+}
+
+/// Builds a full freelist around the objects, as if each had been inserted in order, reusing the
+/// vector as the objects' storage. Fails if there are more than `MAX_SIZE` objects.
+impl<T> std::convert::TryFrom<Vec<T>> for PackedFreelist<T> {
+    type Error = ReserveError;
+
+    fn try_from(objects: Vec<T>) -> Result<Self, Self::Error> {
+        if objects.len() > Self::MAX_SIZE {
+            return Err(ReserveError::ExceedsMaxSize { requested: objects.len(), max: Self::MAX_SIZE });
+        }
+        Ok(Self::from_vec(objects))
+    }
+}
+
+/// Inserts each object under its ID with `insert_with_id`, so the IDs remain valid.
+///
+/// Panics if an ID's allocation is already in use, rather than skipping or overwriting its object,
+/// since either would silently lose an object. Also panics if an ID is of an older generation than
+/// its allocation's current one.
+impl<T, M: Default> Extend<(AllocationID, T)> for PackedFreelist<T, M> {
+    fn extend<I: IntoIterator<Item = (AllocationID, T)>>(&mut self, iter: I) {
+        for (id, value) in iter {
+            if self.insert_with_id(id, value).is_err() {
+                panic!("Can't insert into PackedFreelist with ID {:#x}: its allocation is in use, too large or of a newer generation.", id);
+            }
+        }
     }
 }
 
-impl<T> Deref for PackedFreelist<T> {
+impl<T, M> Deref for PackedFreelist<T, M> {
     type Target = [T];
 
     fn deref(&self) -> &Self::Target {
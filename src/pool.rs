@@ -0,0 +1,104 @@
+//! Recycling of `PackedFreelist`s, so that short lived lists reuse the storage of earlier ones
+//! rather than allocating their own.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+use crate::{GrowHook, GrowthPolicy, PackedFreelist};
+
+/// A pool of idle lists, handed out by `acquire` and returned when the handed out list is dropped.
+///
+/// The pool can be shared between threads, such as by the jobs of a job system.
+#[derive(Debug)]
+pub struct FreelistPool<T> {
+    idle: Mutex<Vec<PackedFreelist<T>>>,
+}
+
+impl<T> FreelistPool<T> {
+    /// Constructs a new, empty `FreelistPool<T>`.
+    pub fn new() -> Self {
+        Self { idle: Mutex::new(Vec::new()) }
+    }
+
+    /// Get an empty list able to hold at least `capacity` objects, reusing an idle list if there
+    /// is one. The list returns to the pool when dropped.
+    ///
+    /// Reused lists are empty, and have the default growth policy, no grow callback and no dense
+    /// indices. Unlike freshly constructed lists, their capacity may be larger than requested, their
+    /// `stats` counts carry over, and their allocations carry on from the generations they reached,
+    /// so IDs handed out to an earlier user of the list never resolve to a later user's objects.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` exceeds `MAX_SIZE`.
+    pub fn acquire(&self, capacity: usize) -> PooledFreelist<'_, T> {
+        let list = match self.lock().pop() {
+            Some(mut list) => {
+                list.reserve(capacity);
+                list
+            },
+            None => { PackedFreelist::with_capacity(capacity) },
+        };
+        PooledFreelist { list: Some(list), pool: self }
+    }
+
+    /// Get the number of idle lists, waiting to be reused.
+    pub fn idle(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Lock the idle lists. A panic while locked can't leave the lists in a broken state, since
+    /// they're only pushed and popped, so the lock is taken even if poisoned.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<PackedFreelist<T>>> {
+        self.idle.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<T> Default for FreelistPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A list handed out by a `FreelistPool`, which is cleared and returned to the pool when dropped.
+///
+/// The list is used through `Deref` and `DerefMut`.
+#[derive(Debug)]
+pub struct PooledFreelist<'a, T> {
+    /// Only None once taken by `into_inner` or while dropping.
+    list: Option<PackedFreelist<T>>,
+    pool: &'a FreelistPool<T>,
+}
+
+impl<'a, T> PooledFreelist<'a, T> {
+    /// Keep the list rather than returning it to the pool.
+    pub fn into_inner(mut self) -> PackedFreelist<T> {
+        self.list.take().unwrap()
+    }
+}
+
+impl<'a, T> Deref for PooledFreelist<'a, T> {
+    type Target = PackedFreelist<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.list.as_ref().unwrap()
+    }
+}
+
+impl<'a, T> DerefMut for PooledFreelist<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.list.as_mut().unwrap()
+    }
+}
+
+impl<'a, T> Drop for PooledFreelist<'a, T> {
+    fn drop(&mut self) {
+        if let Some(mut list) = self.list.take() {
+            list.clear();
+            list.growth_policy = GrowthPolicy::default();
+            list.on_grow = GrowHook::default();
+            list.dense = None;
+            self.pool.lock().push(list);
+        }
+    }
+}
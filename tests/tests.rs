@@ -1,7 +1,10 @@
 mod packed_freelist {
     extern crate rand;
 
-    use packed_freelist::{PackedFreelist, AllocationID};
+    use packed_freelist::{PackedFreelist, AllocationID, AllocationErrorKind, ReserveError, GrowthPolicy, Removal, SlotInfo, AnyFreelist, get_pair_mut, same_slot, is_newer};
+    use packed_freelist::intern::Interner;
+    use packed_freelist::compat::SlotMap;
+    use packed_freelist::pool::FreelistPool;
     use std::error::Error;
     use self::rand::seq::SliceRandom;
 
@@ -30,11 +33,16 @@ mod packed_freelist {
         let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
         assert!(p.is_empty());
 
-        assert!(p.insert(1).is_ok());
+        let id = p.insert(1).unwrap();
         assert!(!p.is_empty());
+
+        p.remove(id);
+        assert!(p.is_empty());
+        assert_eq!(5, p.capacity());
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn contains() {
         {
             let p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
@@ -63,7 +71,12 @@ mod packed_freelist {
             let r = p.insert(v);
             let err = r.unwrap_err();
             assert!(err.source().is_none());
-            assert_eq!(format!("{}", err), format!("Failed to acquire allocation with index {}", v));
+            assert_eq!(err.kind(), AllocationErrorKind::AtCapacity);
+            assert_eq!(err.len(), CAPACITY);
+            assert_eq!(err.capacity(), CAPACITY);
+            assert_eq!(format!("{}", err), format!("Failed to acquire allocation: list is at capacity with {} of {} objects", CAPACITY, CAPACITY));
+            assert_eq!(p.len(), CAPACITY);
+            assert!(p.iter().all(|&x| x != v));
         }
 
         {
@@ -128,6 +141,7 @@ mod packed_freelist {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_fold)]
     fn iterator() {
         {
             let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
@@ -184,4 +198,2553 @@ mod packed_freelist {
             assert!(std::panic::catch_unwind(|| p[3]).is_err());
         }
     }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn bytes() {
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+            let a = p.insert(1).unwrap();
+            assert!(p.insert(2).is_ok());
+            assert!(p.insert(3).is_ok());
+            p.remove(a);
+
+            let q : PackedFreelist<u32> = PackedFreelist::from_bytes(p.as_bytes()).unwrap();
+            assert_eq!(q.len(), 2);
+            assert_eq!(q.capacity(), 2);
+            assert_eq!(&*q, &*p);
+            assert_eq!(q.as_bytes(), p.as_bytes());
+        }
+
+        {
+            let q : PackedFreelist<u32> = PackedFreelist::from_bytes(&[1, 0, 0, 0, 2, 0, 0, 0]).unwrap();
+            let ids: Vec<AllocationID> = (0..q.len() as AllocationID).map(|i| 0x10000 | i).collect();
+            assert!(ids.iter().all(|&id| q.contains(id)));
+            assert_eq!(q[ids[0]], 1);
+            assert_eq!(q[ids[1]], 2);
+
+            assert!(PackedFreelist::<u32>::from_bytes(&[1, 0, 0]).is_err());
+        }
+    }
+
+    #[test]
+    fn index_of() {
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+            let a = p.insert(1).unwrap();
+            let b = p.insert(2).unwrap();
+            let c = p.insert(3).unwrap();
+            assert_eq!(p.index_of(a), Some(0));
+            assert_eq!(p.index_of(b), Some(1));
+            assert_eq!(p.index_of(c), Some(2));
+
+            p.remove(a);
+            assert_eq!(p.index_of(a), None);
+            assert_eq!(p.index_of(c), Some(0));
+            assert_eq!(p.index_of(b), Some(1));
+            assert_eq!(p.index_of(99), None);
+        }
+
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+            let a = p.insert(1).unwrap();
+            p.remove(a);
+            let b = p.insert(2).unwrap();
+            assert_eq!(p.index_of(a), None);
+            assert_eq!(p.index_of(b), Some(0));
+        }
+    }
+
+    #[test]
+    fn handle_at() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        assert_eq!(p.handle_at(0), None);
+
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+        assert_eq!(p.handle_at(0), Some(a));
+        assert_eq!(p.handle_at(1), Some(b));
+        assert_eq!(p.handle_at(2), None);
+
+        p.remove(a);
+        assert_eq!(p.handle_at(0), Some(b));
+        assert_eq!(p.handle_at(1), None);
+        assert_eq!(p.index_of(p.handle_at(0).unwrap()), Some(0));
+    }
+
+    #[test]
+    fn remove_tracked() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+        let c = p.insert(3).unwrap();
+
+        let mut moves = Vec::new();
+        assert_eq!(p.remove_tracked(a, |from, to, id| moves.push((from, to, id))), Some(1));
+        assert_eq!(moves, vec![(2, 0, c)]);
+        assert_eq!(p.index_of(c), Some(0));
+        assert_eq!(p[c], 3);
+
+        moves.clear();
+        assert_eq!(p.remove_tracked(b, |from, to, id| moves.push((from, to, id))), Some(2));
+        assert!(moves.is_empty());
+
+        assert_eq!(p.remove_tracked(a, |from, to, id| moves.push((from, to, id))), None);
+        assert!(moves.is_empty());
+        assert_eq!(p.len(), 1);
+        assert!(p.contains(c));
+    }
+
+    #[test]
+    fn queries() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        assert_eq!(p.count_where(|&v| v > 0), 0);
+        assert!(!p.any(|&v| v > 0));
+        assert!(p.all(|&v| v > 0));
+        assert_eq!(p.fold(0, |a, &c| a + c), 0);
+
+        let a = p.insert(1).unwrap();
+        assert!(p.insert(2).is_ok());
+        assert!(p.insert(3).is_ok());
+        assert_eq!(p.count_where(|&v| v > 1), 2);
+        assert!(p.any(|&v| v == 1));
+        assert!(p.all(|&v| v > 0));
+        assert_eq!(p.fold(0, |a, &c| a + c), 6);
+
+        p.remove(a);
+        assert_eq!(p.count_where(|&v| v > 1), 2);
+        assert!(!p.any(|&v| v == 1));
+        assert!(p.all(|&v| v > 1));
+        assert_eq!(p.fold(0, |a, &c| a + c), 5);
+    }
+
+    #[test]
+    fn reserve_ids() {
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+            let a = p.insert(1).unwrap();
+            let ids = p.reserve_ids(3).unwrap();
+            assert_eq!(ids.len(), 3);
+            assert_eq!(p.len(), 4);
+            assert!(ids.iter().all(|&id| p.contains(id) && p[id] == 0));
+
+            for (i, &id) in ids.iter().enumerate() {
+                assert!(p.fill_reserved(id, 10 + i as u32).is_ok());
+            }
+            assert_eq!(p[a], 1);
+            assert_eq!(p[ids[0]], 10);
+            assert_eq!(p[ids[1]], 11);
+            assert_eq!(p[ids[2]], 12);
+
+            p.remove(ids[1]);
+            assert_eq!(p.fill_reserved(ids[1], 99), Err(99));
+        }
+
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+            assert!(p.insert(1).is_ok());
+            assert!(p.reserve_ids(3).is_err());
+            assert_eq!(p.len(), 1);
+            assert_eq!(p.reserve_ids(2).unwrap().len(), 2);
+            assert_eq!(p.len(), 3);
+        }
+    }
+
+    #[test]
+    fn into_iter_with_ids() {
+        {
+            let p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+            assert_eq!(p.into_iter_with_ids().len(), 0);
+        }
+
+        {
+            let mut p : PackedFreelist<TestStruct> = PackedFreelist::with_capacity(5);
+            let a = p.insert(TestStruct { n: 1 }).unwrap();
+            let b = p.insert(TestStruct { n: 2 }).unwrap();
+            let c = p.insert(TestStruct { n: 3 }).unwrap();
+            p.remove(a);
+
+            let iter = p.into_iter_with_ids();
+            assert_eq!(iter.len(), 2);
+            let pairs: Vec<(AllocationID, u32)> = iter.map(|(id, v)| (id, v.n)).collect();
+            assert_eq!(pairs, vec![(c, 3), (b, 2)]);
+        }
+    }
+
+    #[test]
+    fn growth_policy() {
+        fn capacities(policy: GrowthPolicy, initial: usize, inserts: usize) -> Vec<usize> {
+            let mut p : PackedFreelist<usize> = PackedFreelist::with_capacity(initial);
+            p.set_growth_policy(policy);
+            let mut capacities = vec![p.capacity()];
+            for i in 0..inserts {
+                if p.insert(i).is_err() {
+                    break;
+                }
+                if p.capacity() != *capacities.last().unwrap() {
+                    capacities.push(p.capacity());
+                }
+            }
+            assert!(p.iter().enumerate().all(|(i, &v)| i == v));
+            capacities
+        }
+
+        {
+            let p : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+            match p.growth_policy() {
+                GrowthPolicy::Fixed => {},
+                policy => panic!("unexpected default growth policy {:?}", policy),
+            }
+        }
+
+        assert_eq!(capacities(GrowthPolicy::Fixed, 2, 10), vec![2]);
+        assert_eq!(capacities(GrowthPolicy::Double, 1, 10), vec![1, 2, 4, 8, 16]);
+        assert_eq!(capacities(GrowthPolicy::Additive(3), 2, 10), vec![2, 5, 8, 11]);
+        assert_eq!(capacities(GrowthPolicy::Custom(|c| c + c / 2), 2, 10), vec![2, 3, 4, 6, 9, 13]);
+        assert_eq!(capacities(GrowthPolicy::Custom(|c| c), 2, 10), vec![2]);
+
+        {
+            // the capacity counts allocations, not spare object storage
+            let mut objects = Vec::with_capacity(8);
+            objects.extend_from_slice(&[1u32, 2, 3]);
+            let mut p : PackedFreelist<u32> = std::convert::TryFrom::try_from(objects).unwrap();
+            assert_eq!(p.capacity(), 3);
+            assert!(p.insert(4).is_err());
+            p.set_growth_policy(GrowthPolicy::Double);
+            assert!(p.insert(4).is_ok());
+            assert_eq!(p.capacity(), 6);
+        }
+
+        {
+            // growth keeps existing IDs valid and queues the new allocations after the free ones
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+            p.set_growth_policy(GrowthPolicy::Additive(2));
+            let a = p.insert(1).unwrap();
+            let b = p.insert(2).unwrap();
+            p.remove(a);
+            let c = p.insert(3).unwrap();
+            let d = p.insert(4).unwrap();
+            let e = p.insert(5).unwrap();
+            assert_eq!(p.capacity(), 4);
+            assert!(!p.contains(a));
+            assert_eq!((p[b], p[c], p[d], p[e]), (2, 3, 4, 5));
+            assert!(p.insert(6).is_ok());
+            assert_eq!(p.capacity(), 6);
+        }
+
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+            p.set_growth_policy(GrowthPolicy::Double);
+            assert_eq!(p.reserve_ids(6).unwrap().len(), 6);
+            assert_eq!(p.capacity(), 8);
+        }
+    }
+
+    #[test]
+    fn get_each_mut() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+        let c = p.insert(3).unwrap();
+        p.remove(b);
+
+        {
+            let [x, y, z, w] = p.get_each_mut([c, b, a, c]);
+            assert!(y.is_none());
+            assert!(w.is_none());
+            let (x, z) = (x.unwrap(), z.unwrap());
+            assert_eq!((*x, *z), (3, 1));
+            std::mem::swap(x, z);
+        }
+        assert_eq!(p[a], 3);
+        assert_eq!(p[c], 1);
+
+        let [x] = p.get_each_mut([99]);
+        assert!(x.is_none());
+        let [] = p.get_each_mut([]);
+    }
+
+    #[test]
+    fn shrink_capacity_to() {
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(10);
+            let a = p.insert(1).unwrap();
+            let b = p.insert(2).unwrap();
+            assert_eq!(p.shrink_capacity_to(5), 5);
+            assert_eq!(p.capacity(), 5);
+            assert_eq!((p[a], p[b]), (1, 2));
+
+            // clamps to len
+            assert_eq!(p.shrink_capacity_to(0), 2);
+            assert!(p.insert(3).is_err());
+            p.remove(b);
+            let c = p.insert(3).unwrap();
+            assert_eq!((p[a], p[c]), (1, 3));
+
+            // never grows
+            assert_eq!(p.shrink_capacity_to(8), 2);
+        }
+
+        {
+            // live allocations at the end of the list can't be dropped
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(6);
+            let ids: Vec<AllocationID> = (0..4).map(|v| p.insert(v).unwrap()).collect();
+            p.remove(ids[0]);
+            p.remove(ids[1]);
+            assert_eq!(p.shrink_capacity_to(0), 4);
+            assert_eq!(p.len(), 2);
+            assert_eq!((p[ids[2]], p[ids[3]]), (2, 3));
+
+            // the surviving free allocations are reused
+            let c = p.insert(4).unwrap();
+            let d = p.insert(5).unwrap();
+            assert!(p.insert(6).is_err());
+            assert_eq!((p[c], p[d]), (4, 5));
+            assert_eq!((p[ids[2]], p[ids[3]]), (2, 3));
+        }
+    }
+
+    #[test]
+    fn remove_from_full() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+        p.remove(b);
+        let c = p.insert(3).unwrap();
+        assert!(p.contains(a));
+        assert!(!p.contains(b));
+        assert!(p.contains(c));
+        assert_eq!((p[a], p[c]), (1, 3));
+    }
+
+    #[test]
+    fn eq() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        assert_eq!(p, vec![]);
+        assert_eq!(p, []);
+
+        let a = p.insert(1).unwrap();
+        assert!(p.insert(2).is_ok());
+        assert!(p.insert(3).is_ok());
+        assert_eq!(p, vec![1, 2, 3]);
+        assert_eq!(p, [1, 2, 3]);
+        assert_eq!(p, vec![1, 2, 3][..]);
+        assert_ne!(p, vec![3, 2, 1]);
+
+        p.remove(a);
+        assert_eq!(p, vec![3, 2]);
+        assert_ne!(p, [2, 3]);
+    }
+
+    #[test]
+    fn rollback() {
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+            let a = p.insert(1).unwrap();
+            let b = p.insert(2).unwrap();
+            p.remove(a);
+
+            let cp = p.checkpoint();
+            let c = p.insert(3).unwrap();
+            let d = p.insert(4).unwrap();
+            assert!(p.rollback(cp).is_ok());
+            assert_eq!(p, [2]);
+            assert!(p.contains(b));
+            assert!(!p.contains(c));
+            assert!(!p.contains(d));
+
+            // the rolled back allocations are reused, with new IDs
+            let ids: Vec<AllocationID> = (0..4).map(|v| p.insert(v).unwrap()).collect();
+            assert!(p.insert(5).is_err());
+            assert!(!ids.contains(&c));
+            assert!(!ids.contains(&d));
+            assert_eq!(p, [2, 0, 1, 2, 3]);
+
+            // rolling back nothing
+            let cp = p.checkpoint();
+            assert!(p.rollback(cp).is_ok());
+            assert_eq!(p.len(), 5);
+        }
+
+        {
+            // rolling back a full list
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+            let a = p.insert(1).unwrap();
+            let cp = p.checkpoint();
+            let b = p.insert(2).unwrap();
+            assert!(p.rollback(cp).is_ok());
+            assert!(!p.contains(b));
+            let c = p.insert(3).unwrap();
+            assert!(p.insert(4).is_err());
+            assert_eq!((p[a], p[c]), (1, 3));
+        }
+
+        {
+            // rolling back growth
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+            p.set_growth_policy(GrowthPolicy::Double);
+            let a = p.insert(1).unwrap();
+            let cp = p.checkpoint();
+            assert!(p.insert(2).is_ok());
+            assert!(p.insert(3).is_ok());
+            assert!(p.rollback(cp).is_ok());
+            assert_eq!(p, [1]);
+            assert_eq!(p.capacity(), 4);
+            let ids: Vec<AllocationID> = (0..3).map(|v| p.insert(v).unwrap()).collect();
+            assert_eq!(p.len(), 4);
+            assert!(p.contains(a));
+            assert!(ids.iter().all(|&id| p.contains(id)));
+        }
+
+        {
+            // removals can't be rolled back
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+            let a = p.insert(1).unwrap();
+            let cp = p.checkpoint();
+            let b = p.insert(2).unwrap();
+            p.remove(a);
+            let err = p.rollback(cp).unwrap_err();
+            assert_eq!(format!("{}", err), "Failed to roll back to checkpoint with length 1: the list changed by more than insertions since it was taken");
+            assert_eq!(p, [2]);
+            assert!(p.contains(b));
+        }
+    }
+
+    #[test]
+    fn iter_with_ids() {
+        {
+            let p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+            assert_eq!(p.iter_with_ids().len(), 0);
+            assert!(p.iter_with_ids().next().is_none());
+            assert!(p.iter_with_ids().next_back().is_none());
+        }
+
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+            let a = p.insert(1).unwrap();
+            let b = p.insert(2).unwrap();
+            let c = p.insert(3).unwrap();
+            p.remove(a);
+
+            let pairs: Vec<(AllocationID, u32)> = p.iter_with_ids().map(|(id, &v)| (id, v)).collect();
+            assert_eq!(pairs, vec![(c, 3), (b, 2)]);
+            let pairs: Vec<(AllocationID, u32)> = p.iter_with_ids().rev().map(|(id, &v)| (id, v)).collect();
+            assert_eq!(pairs, vec![(b, 2), (c, 3)]);
+            assert!(p.iter_with_ids().all(|(id, &v)| p[id] == v));
+        }
+
+        {
+            // removing in reverse packed order never moves an object
+            const CAPACITY: usize = 10;
+            let mut p : PackedFreelist<usize> = PackedFreelist::with_capacity(CAPACITY);
+            for i in 0..CAPACITY {
+                assert!(p.insert(i).is_ok());
+            }
+            let ids: Vec<AllocationID> = p.iter_with_ids().rev().map(|(id, _)| id).collect();
+            let mut visited = Vec::new();
+            for id in ids {
+                visited.push(p.remove_tracked(id, |_, _, _| panic!("object moved")).unwrap());
+            }
+            assert!(p.is_empty());
+            assert_eq!(visited, (0..CAPACITY).rev().collect::<Vec<usize>>());
+        }
+    }
+
+    #[test]
+    fn insert_unique() {
+        let mut p : PackedFreelist<&str> = PackedFreelist::with_capacity(3);
+        let a = p.insert_unique("a").unwrap();
+        let b = p.insert_unique("b").unwrap();
+        assert_eq!(p.insert_unique("a").unwrap(), a);
+        assert_eq!(p.insert_unique("b").unwrap(), b);
+        assert_eq!(p.len(), 2);
+
+        p.remove(a);
+        let c = p.insert_unique("a").unwrap();
+        assert_ne!(a, c);
+        assert_eq!(p.insert_unique("a").unwrap(), c);
+        assert!(p.insert_unique("c").is_ok());
+        assert!(p.insert_unique("d").is_err());
+        assert_eq!(p.insert_unique("b").unwrap(), b);
+    }
+
+    #[test]
+    fn interner() {
+        let mut p : Interner<String> = Interner::with_capacity(3);
+        let a = p.insert_unique("a".to_string()).unwrap();
+        let b = p.insert_unique("b".to_string()).unwrap();
+        let c = p.insert_unique("c".to_string()).unwrap();
+        assert_eq!(p.insert_unique("a".to_string()).unwrap(), a);
+        assert_eq!(p.insert_unique("c".to_string()).unwrap(), c);
+        assert_eq!(p.len(), 3);
+        assert_eq!(p.find(&"b".to_string()), Some(b));
+        assert!(p.insert_unique("d".to_string()).is_err());
+
+        // removal swaps "c" into the place of "a", which must not affect lookups
+        assert_eq!(p.remove(a), Some("a".to_string()));
+        assert_eq!(p.remove(a), None);
+        assert_eq!(p.find(&"a".to_string()), None);
+        assert_eq!(p.find(&"c".to_string()), Some(c));
+        assert_eq!(p.insert_unique("c".to_string()).unwrap(), c);
+        assert_eq!(p[c], "c");
+
+        let d = p.insert_unique("a".to_string()).unwrap();
+        assert_ne!(a, d);
+        assert_eq!(p.find(&"a".to_string()), Some(d));
+        assert_eq!(p.into_inner().len(), 3);
+    }
+
+    #[test]
+    #[allow(clippy::manual_is_multiple_of)]
+    fn retain_ids() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(10);
+        let ids: Vec<AllocationID> = (0..10).map(|v| p.insert(v).unwrap()).collect();
+        let keep: Vec<AllocationID> = ids.iter().cloned().filter(|&id| p[id] % 3 == 0).collect();
+        p.retain_ids(keep.clone());
+        assert_eq!(p.len(), 4);
+        assert!(ids.iter().all(|&id| p.contains(id) == keep.contains(&id)));
+        assert!(keep.iter().all(|&id| p[id] % 3 == 0));
+
+        p.retain_ids(vec![ids[3], ids[3] + 0x10000]);
+        assert_eq!(p, [3]);
+        assert!(p.contains(ids[3]));
+
+        p.retain_ids(None);
+        assert!(p.is_empty());
+    }
+
+    #[test]
+    fn remove_ids_not_in() {
+        let mut entities : PackedFreelist<()> = PackedFreelist::with_capacity(5);
+        let mut positions : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        let a = entities.insert(()).unwrap();
+        let b = entities.insert(()).unwrap();
+        let c = entities.insert(()).unwrap();
+        assert_eq!(positions.insert(1).unwrap(), a);
+        assert_eq!(positions.insert(2).unwrap(), b);
+        assert_eq!(positions.insert(3).unwrap(), c);
+
+        entities.remove(b);
+        positions.remove_ids_not_in(&entities);
+        assert_eq!(positions.len(), 2);
+        assert!(!positions.contains(b));
+        assert_eq!((positions[a], positions[c]), (1, 3));
+
+        // a reused allocation has a different generation, so its old ID is gone
+        entities.remove(a);
+        while entities.insert(()).is_ok() {}
+        assert!(!entities.contains(a));
+        assert!(entities.iter_with_ids().any(|(id, _)| id & 0xFFFF == a & 0xFFFF));
+        positions.remove_ids_not_in(&entities);
+        assert_eq!(positions, [3]);
+        assert!(positions.contains(c));
+    }
+
+    #[test]
+    fn generation_wrap() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+        let first = p.insert(2).unwrap();
+        assert_eq!(first >> 16, 1);
+
+        let mut id = first;
+        for generation in 2..=u16::MAX as AllocationID {
+            p.remove(id);
+            id = p.insert(2).unwrap();
+            assert_eq!(id, (generation << 16) | (first & 0xFFFF));
+        }
+
+        p.remove(id);
+        let wrapped = p.insert(2).unwrap();
+        assert_eq!(wrapped >> 16, 0);
+        assert_eq!(wrapped & 0xFFFF, first & 0xFFFF);
+        assert!(p.contains(wrapped));
+        assert!(!p.contains(id));
+
+        p.remove(wrapped);
+        assert_eq!(p.insert(2).unwrap(), first);
+    }
+
+    #[test]
+    fn drain_sorted() {
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+            let ids: Vec<AllocationID> = vec![3, 1, 4, 1, 5].into_iter().map(|v| p.insert(v).unwrap()).collect();
+            p.remove(ids[0]);
+            assert_eq!(p.drain_sorted().collect::<Vec<u32>>(), vec![1, 1, 4, 5]);
+            assert!(p.is_empty());
+            assert_eq!(p.capacity(), 5);
+            assert!(ids.iter().all(|&id| !p.contains(id)));
+
+            // the list is reusable, with new IDs
+            let new_ids: Vec<AllocationID> = (0..5).map(|v| p.insert(v).unwrap()).collect();
+            assert!(new_ids.iter().all(|id| !ids.contains(id)));
+            assert_eq!(p, [0, 1, 2, 3, 4]);
+        }
+
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+            let ids: Vec<AllocationID> = (0..5).map(|v| p.insert(v).unwrap()).collect();
+            {
+                let mut drain = p.drain_sorted_by(|a, b| b.cmp(a));
+                assert_eq!(drain.next(), Some(4));
+            }
+            assert!(p.is_empty());
+            assert!(ids.iter().all(|&id| !p.contains(id)));
+            assert!(p.insert(1).is_ok());
+        }
+    }
+
+    #[test]
+    fn chunks() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(10);
+        assert_eq!(p.chunks(4).count(), 0);
+        assert_eq!(p.array_chunks_mut::<4>().count(), 0);
+
+        for i in 0..10 {
+            assert!(p.insert(i).is_ok());
+        }
+        let sums: Vec<u32> = p.chunks(4).map(|c| c.iter().sum()).collect();
+        assert_eq!(sums, vec![6, 22, 17]);
+
+        p.chunks_mut(4).for_each(|c| c[0] = 100);
+        assert_eq!(p, [100, 1, 2, 3, 100, 5, 6, 7, 100, 9]);
+
+        {
+            let mut chunks = p.chunks_exact_mut(3);
+            assert_eq!(chunks.by_ref().count(), 3);
+            assert_eq!(chunks.into_remainder(), &[9]);
+        }
+
+        for lane in p.array_chunks_mut::<4>() {
+            for v in lane.iter_mut() {
+                *v += 1;
+            }
+        }
+        assert_eq!(p, [101, 2, 3, 4, 101, 6, 7, 8, 100, 9]);
+    }
+
+    #[test]
+    fn iter_by_id() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        assert_eq!(p.iter_by_id().len(), 0);
+
+        let ids: Vec<AllocationID> = (0..5).map(|v| p.insert(v).unwrap()).collect();
+        p.remove(ids[0]);
+        p.remove(ids[2]);
+        assert_eq!(p, [4, 1, 3]);
+
+        let pairs: Vec<(AllocationID, u32)> = p.iter_by_id().map(|(id, &v)| (id, v)).collect();
+        assert_eq!(pairs, vec![(ids[1], 1), (ids[3], 3), (ids[4], 4)]);
+
+        // a reused allocation keeps its place in the order
+        let a = p.insert(5).unwrap();
+        let b = p.insert(6).unwrap();
+        assert_eq!(a & 0xFFFF, ids[0] & 0xFFFF);
+        assert_eq!(b & 0xFFFF, ids[2] & 0xFFFF);
+        let values: Vec<u32> = p.iter_by_id().map(|(_, &v)| v).collect();
+        assert_eq!(values, vec![5, 1, 6, 3, 4]);
+    }
+
+    #[test]
+    fn remove_with_move() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+        let c = p.insert(3).unwrap();
+
+        assert_eq!(p.remove_with_move(a), Some(Removal { value: 1, moved: Some((c, 0)) }));
+        assert_eq!(p.index_of(c), Some(0));
+        assert_eq!(p.remove_with_move(b), Some(Removal { value: 2, moved: None }));
+        assert_eq!(p.remove_with_move(a), None);
+        assert_eq!(p.remove_with_move(c), Some(Removal { value: 3, moved: None }));
+        assert!(p.is_empty());
+    }
+
+    #[test]
+    fn singleton() {
+        let (mut p, a) = PackedFreelist::singleton(TestStruct { n: 7 });
+        assert_eq!(p.len(), 1);
+        assert_eq!(p.capacity(), 1);
+        assert!(p.contains(a));
+        assert_eq!(p[a].n, 7);
+        assert!(p.insert(TestStruct { n: 8 }).is_err());
+
+        p.remove(a);
+        let b = p.insert(TestStruct { n: 8 }).unwrap();
+        assert!(!p.contains(a));
+        assert_eq!(p[b].n, 8);
+    }
+
+    #[test]
+    fn index_panic_message() {
+        fn panic_message<F: FnOnce() -> R + std::panic::UnwindSafe, R>(f: F) -> String {
+            let payload = std::panic::catch_unwind(f).err().unwrap();
+            payload.downcast_ref::<String>().cloned().unwrap()
+        }
+
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        let a = p.insert(1).unwrap();
+        assert!(p.insert(2).is_ok());
+        p.remove(a);
+
+        assert_eq!(panic_message(|| p[0x10009]),
+                   "Invalid AllocationID 0x10009 (allocation 9, generation 1): allocation index is out of range. The list holds 1 objects with capacity 5.");
+        assert_eq!(panic_message(|| p[a]),
+                   "Invalid AllocationID 0x10000 (allocation 0, generation 1): allocation is free. The list holds 1 objects with capacity 5.");
+    }
+
+    #[test]
+    fn extend_from_slice() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        let a = p.insert(1).unwrap();
+        let ids = p.extend_from_slice(&[2, 3, 4]).unwrap();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(p, [1, 2, 3, 4]);
+        assert_eq!(p[a], 1);
+        assert_eq!((p[ids[0]], p[ids[1]], p[ids[2]]), (2, 3, 4));
+
+        assert!(p.extend_from_slice(&[5, 6]).is_err());
+        assert_eq!(p.len(), 4);
+        assert!(p.extend_from_slice(&[]).unwrap().is_empty());
+
+        p.remove(ids[0]);
+        let more = p.extend_from_slice(&[5, 6]).unwrap();
+        assert_eq!(p, [1, 4, 3, 5, 6]);
+        assert_eq!((p[more[0]], p[more[1]], p[ids[2]]), (5, 6, 4));
+
+        let mut q : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+        q.set_growth_policy(GrowthPolicy::Double);
+        assert_eq!(q.extend_from_slice(&[1, 2, 3]).unwrap().len(), 3);
+        assert_eq!(q.capacity(), 4);
+    }
+
+    #[test]
+    fn last_inserted_id() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        assert_eq!(p.last_inserted_id(), None);
+
+        let a = p.insert(1).unwrap();
+        assert_eq!(p.last_inserted_id(), Some(a));
+        let ids = p.extend_from_slice(&[2, 3]).unwrap();
+        assert_eq!(p.last_inserted_id(), Some(ids[1]));
+
+        p.remove(ids[0]);
+        assert_eq!(p.last_inserted_id(), Some(ids[1]));
+        p.remove(ids[1]);
+        assert_eq!(p.last_inserted_id(), None);
+
+        let b = p.insert(4).unwrap();
+        assert_eq!(p.last_inserted_id(), Some(b));
+        assert_eq!(p.drain_sorted().count(), 2);
+        assert_eq!(p.last_inserted_id(), None);
+    }
+
+    #[cfg(feature = "quickcheck")]
+    #[test]
+    fn arbitrary() {
+        use packed_freelist::arbitrary::ArbitraryFreelist;
+
+        fn valid(p: ArbitraryFreelist<u32>) -> bool {
+            p.list.len() == p.live.len()
+                && p.list.len() <= p.list.capacity()
+                && p.live.iter().all(|&id| p.list.contains(id))
+                && p.stale.iter().all(|&id| !p.list.contains(id))
+                && p.list.iter_with_ids().all(|(id, &v)| p.list[id] == v)
+        }
+        quickcheck::quickcheck(valid as fn(ArbitraryFreelist<u32>) -> bool);
+    }
+
+    #[test]
+    fn insert_block() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(6);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+        p.remove(a);
+
+        let block = p.insert_block(vec![3, 4, 5]).unwrap();
+        assert_eq!(block, 1..4);
+        assert_eq!(&(*p)[block.clone()], &[3, 4, 5]);
+        let first = p.handle_at(block.start).unwrap();
+        assert_eq!(p.index_of(first), Some(1));
+        assert_eq!(p[first], 3);
+        assert_eq!(p[b], 2);
+
+        assert!(p.insert_block(vec![6, 7, 8]).is_err());
+        assert_eq!(p, [2, 3, 4, 5]);
+
+        // Removing an object before the block moves the block's last object into the gap.
+        p.remove(b);
+        assert_eq!(p, [5, 3, 4]);
+    }
+
+    #[test]
+    fn insert_empty_block() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+        p.insert(1).unwrap();
+        assert_eq!(p.insert_block(Vec::new()).unwrap(), 1..1);
+        assert_eq!(p, [1]);
+
+        // An empty block fits even in a full list.
+        p.insert(2).unwrap();
+        assert_eq!(p.insert_block(Vec::new()).unwrap(), 2..2);
+    }
+
+    #[test]
+    fn pair_mut() {
+        let mut positions : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+        let mut velocities : PackedFreelist<i32> = PackedFreelist::with_capacity(3);
+        let a = positions.insert(10).unwrap();
+        assert_eq!(velocities.insert(2).unwrap(), a);
+        let b = positions.insert(20).unwrap();
+
+        {
+            let (position, velocity) = get_pair_mut(&mut positions, &mut velocities, a).unwrap();
+            *position = (*position as i32 + *velocity) as u32;
+            *velocity = -*velocity;
+        }
+        assert_eq!(positions[a], 12);
+        assert_eq!(velocities[a], -2);
+
+        assert!(get_pair_mut(&mut positions, &mut velocities, b).is_none());
+        velocities.remove(a);
+        assert!(get_pair_mut(&mut positions, &mut velocities, a).is_none());
+    }
+
+    #[test]
+    fn shift_remove() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(10);
+        let ids : Vec<AllocationID> = (1..10).map(|i| p.insert(i).unwrap()).collect();
+
+        assert_eq!(p.shift_remove(ids[4]), Some(5));
+        assert_eq!(p, [1, 2, 3, 4, 6, 7, 8, 9]);
+        assert_eq!(p.shift_remove(ids[4]), None);
+        for (i, &id) in ids.iter().enumerate().filter(|&(i, _)| i != 4) {
+            assert_eq!(p[id], i as u32 + 1);
+        }
+        assert_eq!(p.index_of(ids[8]), Some(7));
+
+        assert_eq!(p.shift_remove(ids[8]), Some(9));
+        assert_eq!(p.shift_remove(ids[0]), Some(1));
+        assert_eq!(p, [2, 3, 4, 6, 7, 8]);
+        assert_eq!(p.index_of(ids[1]), Some(0));
+
+        let c = p.insert(10).unwrap();
+        assert_eq!(p.index_of(c), Some(6));
+    }
+
+    #[test]
+    fn on_grow() {
+        use std::sync::{Arc, Mutex};
+
+        let grown = Arc::new(Mutex::new(Vec::new()));
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+        p.set_growth_policy(GrowthPolicy::Double);
+        {
+            let grown = grown.clone();
+            p.set_on_grow(move |old, new| grown.lock().unwrap().push((old, new)));
+        }
+
+        for i in 0..5 {
+            p.insert(i).unwrap();
+        }
+        assert_eq!(*grown.lock().unwrap(), vec![(2, 4), (4, 8)]);
+
+        p.extend_from_slice(&[5, 6, 7, 8]).unwrap();
+        assert_eq!(*grown.lock().unwrap(), vec![(2, 4), (4, 8), (8, 16)]);
+
+        let mut q = p.clone();
+        q.extend_from_slice(&[0; 8]).unwrap();
+        assert_eq!(q.capacity(), 32);
+        assert_eq!(grown.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn remove_if_present() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+
+        assert!(p.remove_if_present(a));
+        assert!(!p.remove_if_present(a));
+        assert_eq!(p, [2]);
+
+        // The allocation of `a` is reused by a new generation.
+        let c = p.insert(3).unwrap();
+        assert_eq!(c & 0xFFFF, a & 0xFFFF);
+        assert!(!p.remove_if_present(a));
+        assert!(!p.remove_if_present(0xFFFF));
+        assert_eq!(p, [2, 3]);
+
+        assert!(p.remove_if_present(b));
+        assert_eq!(p, [3]);
+    }
+
+    #[test]
+    fn live_slot_indices() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        assert_eq!(p.live_slot_indices().len(), 0);
+
+        let ids : Vec<AllocationID> = (0..4).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[1]);
+        let mut slots : Vec<usize> = p.live_slot_indices().collect();
+        assert_eq!(slots, [0, 3, 2]);
+
+        let a = p.insert(4).unwrap();
+        slots = p.live_slot_indices().collect();
+        slots.sort_unstable();
+        assert_eq!(slots, [0, 2, 3, (a & 0xFFFF) as usize]);
+    }
+
+    #[test]
+    fn max_size() {
+        const MAX : usize = PackedFreelist::<u32>::MAX_SIZE;
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(MAX);
+        assert_eq!(p.capacity(), MAX);
+        assert_eq!(p.max_size(), MAX);
+        assert!(!p.is_at_max_size());
+
+        let ids : Vec<AllocationID> = (0..MAX as u32).map(|i| p.insert(i).unwrap()).collect();
+        assert_eq!(ids.last().map(|&id| id & 0xFFFF), Some(MAX as u32 - 1));
+        assert!(p.is_at_max_size());
+        assert_eq!(p.insert(0).unwrap_err().kind(), AllocationErrorKind::AtCapacity);
+        assert!(ids.iter().enumerate().all(|(i, &id)| p[id] == i as u32));
+
+        // The free chain wraps around to reuse the first allocation.
+        p.remove(ids[0]);
+        let a = p.insert(MAX as u32).unwrap();
+        assert_eq!(a, 0x20000);
+        assert_eq!(p[a], MAX as u32);
+
+        let mut q : PackedFreelist<u32> = PackedFreelist::with_capacity(MAX - 1);
+        q.set_growth_policy(GrowthPolicy::Double);
+        q.extend_from_slice(&vec![0; MAX]).unwrap();
+        assert_eq!(q.capacity(), MAX);
+        assert!(q.insert(0).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn over_max_size() {
+        let _p : PackedFreelist<u32> = PackedFreelist::with_capacity(PackedFreelist::<u32>::MAX_SIZE + 1);
+    }
+
+    #[test]
+    fn iter_full() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(4);
+        let ids : Vec<AllocationID> = (0..4).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[1]);
+
+        let full : Vec<(usize, AllocationID, u32)> = p.iter_full().map(|(i, id, &v)| (i, id, v)).collect();
+        assert_eq!(full, [(0, ids[0], 0), (1, ids[3], 3), (2, ids[2], 2)]);
+        assert!(p.iter_full().all(|(i, id, _)| p.index_of(id) == Some(i)));
+    }
+
+    #[test]
+    fn bulk_contains() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+        let c = p.insert(3).unwrap();
+        p.remove(b);
+        // Stale: the allocation of `b` is reused by a new generation.
+        let d = p.insert(4).unwrap();
+        assert_eq!(d & 0xFFFF, b & 0xFFFF);
+
+        assert!(p.contains_all(vec![a, c, d]));
+        assert!(!p.contains_all(vec![a, b]));
+        assert!(p.contains_all(Vec::new()));
+        assert!(p.contains_any(vec![b, 0xFFFF, c]));
+        assert!(!p.contains_any(vec![b, 0xFFFF]));
+        assert!(!p.contains_any(Vec::new()));
+        assert_eq!(p.filter_live(vec![d, b, a, 0xFFFF]), [d, a]);
+
+        // Short-circuits at the first deciding ID.
+        let mut checked = 0;
+        assert!(!p.contains_all([a, b, c].iter().inspect(|_| checked += 1).cloned()));
+        assert_eq!(checked, 2);
+        checked = 0;
+        assert!(p.contains_any([b, a, c].iter().inspect(|_| checked += 1).cloned()));
+        assert_eq!(checked, 2);
+    }
+
+    #[test]
+    fn reset() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+        let run = |p: &mut PackedFreelist<u32>| {
+            let a = p.insert(1).unwrap();
+            let b = p.insert(2).unwrap();
+            p.remove(a);
+            let c = p.insert(3).unwrap();
+            let d = p.insert(4).unwrap();
+            vec![a, b, c, d]
+        };
+
+        let first = run(&mut p);
+        let checkpoint = p.checkpoint();
+        p.reset();
+        assert!(p.is_empty());
+        assert_eq!(p.capacity(), 3);
+        assert_eq!(p.last_inserted_id(), None);
+        assert!(p.rollback(checkpoint).is_err());
+        assert!(!p.contains_any(first.clone()));
+
+        let second = run(&mut p);
+        assert_eq!(first, second);
+        // Old IDs validate against the new objects.
+        assert_eq!(p[first[1]], 2);
+
+        let mut q : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+        assert_eq!(run(&mut q), first);
+    }
+
+    #[test]
+    fn partition_in_place() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(10);
+        let ids : Vec<AllocationID> = (0..10).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[4]);
+
+        let mut calls = 0;
+        let pivot = p.partition_in_place(|&v| { calls += 1; v % 3 == 0 });
+        assert_eq!(calls, 9);
+        assert_eq!(pivot, 4);
+        assert!(p.iter().take(pivot).all(|&v| v % 3 == 0));
+        assert!(p.iter().skip(pivot).all(|&v| v % 3 != 0));
+        for (i, &id) in ids.iter().enumerate().filter(|&(i, _)| i != 4) {
+            assert_eq!(p[id], i as u32);
+        }
+        assert!(p.iter_full().all(|(i, id, _)| p.index_of(id) == Some(i)));
+
+        assert_eq!(p.partition_in_place(|_| true), 9);
+        assert_eq!(p.partition_in_place(|_| false), 0);
+        let mut q : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+        assert_eq!(q.partition_in_place(|_| true), 0);
+    }
+
+    #[test]
+    fn slot_map() {
+        let mut m : SlotMap<&str> = SlotMap::new();
+        assert!(m.is_empty());
+        let a = m.insert("a");
+        let b = m.insert("b");
+        let c = m.insert("c");
+        assert_eq!(m.len(), 3);
+        assert!(m.capacity() >= 3);
+
+        assert_eq!(m.get(a), Some(&"a"));
+        assert_eq!(m[b], "b");
+        *m.get_mut(c).unwrap() = "C";
+        m[a] = "A";
+        assert_eq!(m.values().cloned().collect::<Vec<_>>(), ["A", "b", "C"]);
+
+        assert_eq!(m.remove(a), Some("A"));
+        assert_eq!(m.remove(a), None);
+        assert!(!m.contains_key(a));
+        assert_eq!(m.get(a), None);
+        assert!(m.get_mut(a).is_none());
+        assert_eq!(m.keys().collect::<Vec<_>>(), [c, b]);
+        assert_eq!(m.iter().map(|(k, &v)| (k, v)).collect::<Vec<_>>(), [(c, "C"), (b, "b")]);
+
+        for (k, v) in m.iter_mut() {
+            if k == b {
+                *v = "B";
+            }
+        }
+        for v in m.values_mut() {
+            *v = if *v == "B" { "bb" } else { *v };
+        }
+        assert_eq!(m[b], "bb");
+
+        m.clear();
+        assert!(m.is_empty());
+        assert!(!m.contains_key(b) && !m.contains_key(c));
+        let d = m.insert("d");
+        assert_eq!(m.into_inner().index_of(d), Some(0));
+
+        let mut m : SlotMap<u32> = SlotMap::default();
+        let a = m.insert(1);
+        m.remove(a);
+        assert!(std::panic::catch_unwind(|| m[a]).is_err());
+    }
+
+    #[test]
+    fn push_pop() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+        assert_eq!(p.pop(), None);
+        assert_eq!(p.push(1).unwrap(), 0);
+        assert_eq!(p.push(2).unwrap(), 1);
+        let b = p.last_inserted_id().unwrap();
+        assert_eq!(p.handle_at(1), Some(b));
+
+        let c = p.insert(3).unwrap();
+        assert!(p.push(4).is_err());
+        assert_eq!(p.pop(), Some(3));
+        assert!(!p.contains(c));
+        assert_eq!(p[b], 2);
+
+        let a = p.handle_at(0).unwrap();
+        p.remove(a);
+        assert_eq!(p.push(5).unwrap(), 1);
+        assert_eq!(p, [2, 5]);
+        assert_eq!(p.pop(), Some(5));
+        assert_eq!(p.pop(), Some(2));
+        assert!(p.is_empty());
+    }
+
+    #[test]
+    fn contains_value() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(200);
+        let ids : Vec<AllocationID> = (0..200).map(|i| p.insert(i * 2).unwrap()).collect();
+        p.remove(ids[10]);
+        assert!(p.contains_value(&0));
+        assert!(p.contains_value(&398));
+        assert!(!p.contains_value(&20));
+        assert!(!p.contains_value(&3));
+    }
+
+    #[test]
+    fn can_hold() {
+        assert_eq!(PackedFreelist::<u32>::max_capacity(), PackedFreelist::<u32>::MAX_SIZE);
+        assert!(PackedFreelist::<u32>::can_hold(0));
+        assert!(PackedFreelist::<u32>::can_hold(PackedFreelist::<u32>::max_capacity()));
+        assert!(!PackedFreelist::<u32>::can_hold(PackedFreelist::<u32>::max_capacity() + 1));
+    }
+
+    #[test]
+    fn remove_aliasing() {
+        // The removed object's allocation is adjacent to the last object's allocation.
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(4);
+        let ids : Vec<AllocationID> = (0..4).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[2]);
+        assert_eq!(p, [0, 1, 3]);
+        assert_eq!(p.index_of(ids[3]), Some(2));
+        assert_eq!((p[ids[0]], p[ids[1]], p[ids[3]]), (0, 1, 3));
+
+        // The list is full, so the tail of the free queue is the allocation of the last object.
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+        let c = p.insert(3).unwrap();
+        p.remove(c);
+        p.remove(a);
+        let d = p.insert(4).unwrap();
+        let e = p.insert(5).unwrap();
+        assert_eq!((d & 0xFFFF, e & 0xFFFF), (c & 0xFFFF, a & 0xFFFF));
+        assert_eq!((p[b], p[d], p[e]), (2, 4, 5));
+        assert!(p.insert(6).is_err());
+
+        // Emptying a full list queues its allocations in the order they're freed.
+        p.remove(e);
+        p.remove(d);
+        p.remove(b);
+        assert!(p.is_empty());
+        let reused : Vec<AllocationID> = (0..3).map(|i| p.insert(i).unwrap() & 0xFFFF).collect();
+        assert_eq!(reused, [a & 0xFFFF, c & 0xFFFF, b & 0xFFFF]);
+        assert_eq!(p, [0, 1, 2]);
+    }
+
+    #[test]
+    fn into_boxed_slice() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(10);
+        let ids : Vec<AllocationID> = (0..3).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[0]);
+
+        assert_eq!(p.clone().into_id_boxed_slice(), vec![(ids[2], 2), (ids[1], 1)].into_boxed_slice());
+        assert_eq!(p.into_boxed_slice(), vec![2, 1].into_boxed_slice());
+    }
+
+    #[test]
+    fn reserve() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+        let a = p.insert(1).unwrap();
+        p.insert(2).unwrap();
+
+        assert_eq!(p.reserve_and_report(1), 3..3);
+        assert_eq!(p.reserve_and_report(4), 3..6);
+        assert_eq!(p.capacity(), 6);
+        p.reserve(2);
+        assert_eq!(p.capacity(), 6);
+
+        // The allocation that was already free comes first, then the new ones in order.
+        p.remove(a);
+        let slots : Vec<AllocationID> = (0..5).map(|i| p.insert(i).unwrap() & 0xFFFF).collect();
+        assert_eq!(slots, [2, 3, 4, 5, 0]);
+        assert!(p.insert(0).is_err());
+
+        assert!(std::panic::catch_unwind(move || p.reserve(PackedFreelist::<u32>::MAX_SIZE)).is_err());
+    }
+
+    #[test]
+    fn index_by_position() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+        let a = p.insert(10).unwrap();
+        let b = p.insert(20).unwrap();
+        let c = p.insert(30).unwrap();
+        p.remove(a);
+
+        assert_eq!((p.get_at(0), p.get_at(1)), (Some(&30), Some(&20)));
+        assert_eq!((p[b], p[c]), (20, 30));
+        assert_eq!(p.get_at(2), None);
+
+        *p.get_at_mut(0).unwrap() += 1;
+        assert_eq!(p[c], 31);
+        assert!(p.get_at_mut(2).is_none());
+    }
+
+    #[test]
+    fn modify() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+
+        {
+            let (id, value) = p.get_mut_with_id(b).unwrap();
+            assert_eq!(id, b);
+            *value = 20;
+        }
+        assert_eq!(p.modify(a, |v| { *v += 10; *v }), Some(11));
+        assert_eq!(p, [11, 20]);
+
+        p.remove(a);
+        assert!(p.get_mut_with_id(a).is_none());
+        let mut called = false;
+        assert_eq!(p.modify(a, |_| called = true), None);
+        assert!(!called);
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn binary() {
+        use packed_freelist::binary::DecodeErrorKind;
+
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        let ids : Vec<AllocationID> = (0..4).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[1]);
+        p.remove(ids[3]);
+        let bytes = p.encode();
+
+        let mut q : PackedFreelist<u32> = PackedFreelist::decode(&bytes).unwrap();
+        assert_eq!(q, [0, 2]);
+        assert_eq!(q.capacity(), 5);
+        assert!(q.iter_with_ids().eq(p.iter_with_ids()));
+        assert!(!q.contains(ids[1]));
+        let inserted : Vec<AllocationID> = (0..3).map(|i| q.insert(i).unwrap()).collect();
+        assert_eq!(inserted, (0..3).map(|i| p.insert(i).unwrap()).collect::<Vec<_>>());
+
+        // Unaligned input.
+        let mut unaligned = vec![0];
+        unaligned.extend_from_slice(&bytes);
+        assert_eq!(PackedFreelist::<u32>::decode(&unaligned[1..]).unwrap(), [0, 2]);
+
+        let mut empty : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+        empty.shrink_capacity_to(0);
+        assert_eq!(PackedFreelist::<u32>::decode(&empty.encode()).unwrap().capacity(), 0);
+
+        let kind = |bytes: &[u8]| PackedFreelist::<u32>::decode(bytes).unwrap_err().kind();
+        assert_eq!(kind(&bytes[..bytes.len() - 1]), DecodeErrorKind::Truncated);
+        assert_eq!(kind(&[bytes.clone(), vec![0]].concat()), DecodeErrorKind::TrailingBytes);
+        let mut too_large = bytes.clone();
+        too_large[4] = 6;
+        assert_eq!(kind(&too_large), DecodeErrorKind::TooLarge);
+
+        // The second object claims the first object's ID.
+        let mut duplicate = bytes.clone();
+        let ids_start = 12 + 5 * 6;
+        duplicate.copy_within(ids_start..ids_start + 4, ids_start + 4);
+        assert_eq!(kind(&duplicate), DecodeErrorKind::Inconsistent);
+
+        // The free queue starts at a live allocation.
+        let mut queue = bytes.clone();
+        queue[8] = 0;
+        assert_eq!(kind(&queue), DecodeErrorKind::Inconsistent);
+    }
+
+    #[test]
+    fn retain_stable() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(10);
+        let ids : Vec<AllocationID> = (0..10).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[2]);
+        assert_eq!(p, [0, 1, 9, 3, 4, 5, 6, 7, 8]);
+
+        let mut visited = Vec::new();
+        p.retain_stable(|&v| { visited.push(v); v % 3 != 0 });
+        assert_eq!(visited, [0, 1, 9, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(p, [1, 4, 5, 7, 8]);
+        for &i in &[1, 4, 5, 7, 8] {
+            assert_eq!(p[ids[i]], i as u32);
+        }
+        for &i in &[0, 3, 6, 9] {
+            assert!(!p.contains(ids[i]));
+        }
+
+        p.retain_stable(|_| true);
+        assert_eq!(p, [1, 4, 5, 7, 8]);
+        p.retain_stable(|_| false);
+        assert!(p.is_empty());
+        assert_eq!(p.extend_from_slice(&[0; 10]).unwrap().len(), 10);
+    }
+
+    #[test]
+    fn id_iterators_stop_at_len() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(8);
+        let counts = |p: &PackedFreelist<u32>| [
+            p.iter_with_ids().count(),
+            p.iter_with_ids().rev().count(),
+            p.iter_with_ids().len(),
+            p.iter_by_id().count(),
+            p.iter_full().count(),
+            p.live_slot_indices().count(),
+            p.clone().into_iter_with_ids().count(),
+            p.clone().into_id_boxed_slice().len(),
+        ];
+
+        assert_eq!(counts(&p), [0; 8]);
+        assert_eq!(p.handle_at(0), None);
+
+        let ids : Vec<AllocationID> = (0..3).map(|i| p.insert(i).unwrap()).collect();
+        assert_eq!(counts(&p), [3; 8]);
+        assert_eq!(p.handle_at(3), None);
+        assert!(p.iter_with_ids().all(|(id, _)| ids.contains(&id)));
+
+        p.remove(ids[0]);
+        assert_eq!(counts(&p), [2; 8]);
+        assert_eq!(p.handle_at(2), None);
+
+        let mut m : SlotMap<u32> = SlotMap::with_capacity(8);
+        assert_eq!((m.keys().count(), m.iter().count(), m.iter_mut().count()), (0, 0, 0));
+        m.insert(1);
+        assert_eq!((m.keys().count(), m.iter().count(), m.iter_mut().count()), (1, 1, 1));
+    }
+
+    #[test]
+    fn for_each_mut() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(4);
+        let ids : Vec<AllocationID> = (0..4).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[1]);
+
+        let mut visited = Vec::new();
+        p.for_each_mut(|id, v| {
+            visited.push(id);
+            *v *= 10;
+        });
+        assert_eq!(visited, [ids[0], ids[3], ids[2]]);
+        assert_eq!(p, [0, 30, 20]);
+    }
+
+    #[test]
+    fn reserve_saturating() {
+        const MAX : usize = PackedFreelist::<u32>::MAX_SIZE;
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+        let a = p.insert(1).unwrap();
+
+        assert_eq!(p.reserve_saturating(1), 0);
+        assert_eq!(p.reserve_saturating(3), 2);
+        assert_eq!(p.capacity(), 4);
+        assert_eq!(p.reserve_saturating(usize::MAX), MAX - 4);
+        assert_eq!(p.capacity(), MAX);
+        assert_eq!(p.reserve_saturating(1), 0);
+
+        assert_eq!(p[a], 1);
+        assert_eq!(p.extend_from_slice(&vec![0; MAX - 1]).unwrap().len(), MAX - 1);
+        assert!(p.insert(0).is_err());
+    }
+
+    #[test]
+    fn handle_comparison() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+        p.remove(a);
+        let c = p.insert(3).unwrap();
+
+        assert!(same_slot(a, c));
+        assert!(!same_slot(a, b));
+        assert_eq!(is_newer(c, a), Some(true));
+        assert_eq!(is_newer(a, c), Some(false));
+        assert_eq!(is_newer(a, a), Some(false));
+        assert_eq!(is_newer(a, b), None);
+
+        // Generations wrap around.
+        assert_eq!(is_newer(0x0000_0001, 0xFFFF_0001), Some(true));
+        assert_eq!(is_newer(0xFFFF_0001, 0x0000_0001), Some(false));
+    }
+
+    #[test]
+    fn drain_while() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(6);
+        let ids : Vec<AllocationID> = [1, 2, 3, 7, 4, 5].iter().map(|&i| p.insert(i).unwrap()).collect();
+
+        assert_eq!(p.drain_while(|&v| v < 3), [1, 2]);
+        assert_eq!(p, [3, 7, 4, 5]);
+        assert!(!p.contains(ids[0]) && !p.contains(ids[1]));
+        assert_eq!(p.index_of(ids[3]), Some(1));
+        assert_eq!(p[ids[5]], 5);
+
+        assert_eq!(p.drain_while(|&v| v < 3), []);
+        assert_eq!(p.drain_while(|&v| v < 5), [3]);
+        assert_eq!(p, [7, 4, 5]);
+
+        // The freed allocations are reused in the order they were drained.
+        let slots : Vec<AllocationID> = (0..3).map(|i| p.insert(i).unwrap() & 0xFFFF).collect();
+        assert_eq!(slots, [0, 1, 2]);
+        assert_eq!(p.drain_while(|_| true).len(), 6);
+        assert!(p.is_empty());
+    }
+
+    #[test]
+    fn entries() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+        let ids : Vec<AllocationID> = (0..3).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[0]);
+
+        assert_eq!(p.entries().len(), 2);
+        let entries : Vec<(AllocationID, u32)> = p.entries().map(|e| (e.id(), *e.value())).collect();
+        assert_eq!(entries, [(ids[2], 2), (ids[1], 1)]);
+
+        for mut entry in p.entries_mut() {
+            if entry.id() == ids[1] {
+                *entry.value_mut() += 10;
+            }
+            assert!(*entry.value() > 0);
+        }
+        let last = p.entries_mut().last().unwrap().into_value_mut();
+        *last += 100;
+        assert_eq!(p, [2, 111]);
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(4);
+        let ids : Vec<AllocationID> = (0..4).map(|i| p.insert(i).unwrap()).collect();
+        let mut q = p.clone();
+
+        p.remove(ids[0]);
+        q.remove(ids[1]);
+        q.remove(ids[0]);
+        let c = q.insert(1).unwrap();
+        assert_eq!(c & 0xFFFF, ids[1] & 0xFFFF);
+        assert_eq!(p, [3, 1, 2]);
+        assert_eq!(q, [2, 3, 1]);
+
+        assert_eq!(p.snapshot(), [(ids[1], 1), (ids[2], 2), (ids[3], 3)]);
+        assert_eq!(q.snapshot(), [(c, 1), (ids[2], 2), (ids[3], 3)]);
+    }
+
+    #[test]
+    fn remove_reporting_move() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+        let c = p.insert(3).unwrap();
+
+        assert_eq!(p.remove_reporting_move(a), (Some(1), Some(c)));
+        assert_eq!(p.index_of(c), Some(0));
+        assert_eq!(p.remove_reporting_move(b), (Some(2), None));
+        assert_eq!(p.remove_reporting_move(b), (None, None));
+        assert_eq!(p, [3]);
+    }
+
+    #[test]
+    fn into_sorted_vec() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        let ids : Vec<AllocationID> = [4, 1, 3, 5, 2].iter().map(|&i| p.insert(i).unwrap()).collect();
+        p.remove(ids[3]);
+
+        assert_eq!(p.clone().into_sorted_vec(), [1, 2, 3, 4]);
+        assert_eq!(p.clone().into_sorted_vec_by(|a, b| b.cmp(a)), [4, 3, 2, 1]);
+        // Stable: equal keys keep their packed order.
+        assert_eq!(p.into_sorted_vec_by(|a, b| (a % 2).cmp(&(b % 2))), [4, 2, 1, 3]);
+    }
+
+    #[test]
+    fn with_capacity_seeded() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity_seeded(2, 100);
+        let a = p.insert(1).unwrap();
+        assert_eq!(a >> 16, 101);
+        p.remove(a);
+        assert_eq!(p.insert(2).unwrap() >> 16, 101);
+        assert_eq!(p.insert(3).unwrap() >> 16, 102);
+
+        p.set_growth_policy(GrowthPolicy::Additive(1));
+        assert_eq!(p.insert(4).unwrap(), (101 << 16) | 2);
+
+        p.reset();
+        assert_eq!(p.insert(5).unwrap(), (101 << 16));
+
+        let mut q : PackedFreelist<u32> = PackedFreelist::with_capacity_seeded(1, u16::MAX - 1);
+        assert_eq!(q.insert(1).unwrap() >> 16, u16::MAX as u32);
+        assert!(std::panic::catch_unwind(|| PackedFreelist::<u32>::with_capacity_seeded(1, u16::MAX)).is_err());
+    }
+
+    #[test]
+    fn filter_ids() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(6);
+        let ids : Vec<AllocationID> = (0..6).map(|i| p.insert(i).unwrap()).collect();
+
+        let odd : Vec<AllocationID> = p.filter_ids(|&v| v % 2 == 1).collect();
+        assert_eq!(odd, [ids[1], ids[3], ids[5]]);
+        for id in odd {
+            p.remove(id);
+        }
+        assert_eq!(p, [0, 4, 2]);
+        assert_eq!(p.filter_ids(|&v| v > 10).count(), 0);
+    }
+
+    #[test]
+    fn rotate() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        let ids : Vec<AllocationID> = (0..5).map(|i| p.insert(i).unwrap()).collect();
+
+        p.rotate_left(2);
+        assert_eq!(p, [2, 3, 4, 0, 1]);
+        assert!(ids.iter().enumerate().all(|(i, &id)| p[id] == i as u32));
+        assert_eq!(p.index_of(ids[0]), Some(3));
+
+        p.rotate_right(3);
+        assert_eq!(p, [4, 0, 1, 2, 3]);
+        assert!(ids.iter().enumerate().all(|(i, &id)| p[id] == i as u32));
+
+        p.remove(ids[4]);
+        assert_eq!(p, [3, 0, 1, 2]);
+        p.rotate_left(4);
+        p.rotate_right(0);
+        assert_eq!(p, [3, 0, 1, 2]);
+        assert!(std::panic::catch_unwind(move || p.rotate_left(5)).is_err());
+    }
+
+    #[test]
+    fn growth_clamped_to_max_size() {
+        const MAX : usize = PackedFreelist::<u32>::MAX_SIZE;
+
+        // Doubling 40000 would overshoot.
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(40000);
+        p.set_growth_policy(GrowthPolicy::Double);
+        for i in 0..40001 {
+            p.insert(i).unwrap();
+        }
+        assert_eq!(p.capacity(), MAX);
+
+        // Growing one object at a time all the way up.
+        let mut q : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+        q.set_growth_policy(GrowthPolicy::Double);
+        let ids : Vec<AllocationID> = (0..MAX as u32).map(|i| q.insert(i).unwrap()).collect();
+        assert_eq!(q.capacity(), MAX);
+        let err = q.insert(0).unwrap_err();
+        assert_eq!(err.kind(), AllocationErrorKind::AtCapacity);
+        assert_eq!((err.len(), err.capacity()), (MAX, MAX));
+        assert_eq!(q[ids[MAX - 1]], MAX as u32 - 1);
+
+        let mut r : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+        r.set_growth_policy(GrowthPolicy::Custom(|_| usize::MAX));
+        r.insert(0).unwrap();
+        r.insert(1).unwrap();
+        assert_eq!(r.capacity(), MAX);
+    }
+
+    #[test]
+    fn remove_many() {
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+            let ids : Vec<AllocationID> = (0..5).map(|i| p.insert(i).unwrap()).collect();
+            // The last object is removed too, along with one it would otherwise fill the gap of.
+            assert_eq!(p.remove_many(vec![ids[1], ids[4], ids[1], 0xFFFF]), 2);
+            assert_eq!(p, [0, 3, 2]);
+            assert_eq!(p.remove_many(vec![ids[4]]), 0);
+            assert_eq!(p.remove_many(Vec::new()), 0);
+        }
+
+        {
+            const MAX_OBJECTS: usize = 100;
+            let rng = &mut rand::thread_rng();
+            for _ in 0..10 {
+                let mut p : PackedFreelist<usize> = PackedFreelist::with_capacity(MAX_OBJECTS);
+                let ids : Vec<AllocationID> = (0..MAX_OBJECTS).map(|i| p.insert(i).unwrap()).collect();
+                let mut shuffled = ids.clone();
+                shuffled.shuffle(rng);
+                let (removed, kept) = shuffled.split_at(MAX_OBJECTS / 3);
+
+                assert_eq!(p.remove_many(removed.iter().cloned()), removed.len());
+                assert_eq!(p.len(), kept.len());
+                assert!(removed.iter().all(|&id| !p.contains(id)));
+                assert!(kept.iter().all(|&id| p[id] == ids.iter().position(|&other| other == id).unwrap()));
+            }
+        }
+    }
+
+    #[test]
+    fn insert_with_id() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(4);
+        let ids : Vec<AllocationID> = (0..4).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[0]);
+        p.remove(ids[2]);
+        let a = p.insert(10).unwrap();
+        let saved : Vec<(AllocationID, u32)> = p.iter_with_ids().map(|(id, &v)| (id, v)).collect();
+
+        // Restore into a smaller list, which grows to fit, with allocations freed out of order.
+        let mut q : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+        q.extend(saved.iter().cloned());
+        assert_eq!(q.len(), 3);
+        assert_eq!(q.capacity(), 4);
+        for &(id, v) in &saved {
+            assert_eq!(q[id], v);
+        }
+        assert_eq!(q.last_inserted_id(), saved.last().map(|&(id, _)| id));
+
+        assert_eq!(q.insert_with_id(a, 20), Err(20));
+        assert_eq!(q.insert_with_id(0xFFFF, 20), Err(20));
+        assert!(q.insert(4).is_ok());
+        assert!(q.insert(5).is_err());
+
+        // Taking the tail of the free queue.
+        let mut r : PackedFreelist<u32> = PackedFreelist::with_capacity(3);
+        assert_eq!(r.insert_with_id(0x50002, 1), Ok(()));
+        assert_eq!(r.insert_with_id(0x50001, 2), Ok(()));
+        assert_eq!(r.insert(3).unwrap() & 0xFFFF, 0);
+
+        let mut s : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+        assert!(std::panic::catch_unwind(move || s.extend(vec![(0x10000, 1), (0x20000, 2)])).is_err());
+    }
+
+    #[test]
+    fn stats() {
+        let mut p = PackedFreelist::with_capacity(4);
+        let a = p.insert(1u32).unwrap();
+        p.insert(2).unwrap();
+        p.remove(a);
+        let checkpoint = p.checkpoint();
+        p.insert(3).unwrap();
+        p.rollback(checkpoint).unwrap();
+
+        let stats = p.stats();
+        assert_eq!(3, stats.total_inserts);
+        assert_eq!(2, stats.total_removes);
+        assert_eq!(1, stats.len);
+        assert_eq!(4, stats.capacity);
+
+        p.reset();
+        p.insert(4).unwrap();
+        let stats = p.stats();
+        assert_eq!(4, stats.total_inserts);
+        assert_eq!(3, stats.total_removes);
+        assert_eq!(stats.total_inserts - stats.total_removes, stats.len as u64);
+    }
+
+    #[test]
+    fn reserve_objects() {
+        let mut p = PackedFreelist::with_capacity(2);
+        p.set_growth_policy(GrowthPolicy::Double);
+        p.insert(0u32).unwrap();
+        p.reserve_objects(15);
+        assert_eq!(2, p.capacity());
+
+        let ptr = p.as_ptr();
+        for i in 1..16 {
+            p.insert(i).unwrap();
+        }
+        assert!(p.capacity() >= 16);
+        assert_eq!(ptr, p.as_ptr());
+
+        {
+            // growing within the reserved storage
+            let mut p = PackedFreelist::with_capacity(8);
+            p.set_growth_policy(GrowthPolicy::Additive(2));
+            for i in 0..8u32 {
+                p.insert(i).unwrap();
+            }
+            p.reserve_objects(2);
+            let ptr = p.as_ptr();
+            p.insert(8).unwrap();
+            p.insert(9).unwrap();
+            assert_eq!(10, p.capacity());
+            assert_eq!(ptr, p.as_ptr());
+        }
+
+        {
+            // growing past the reserved storage
+            let mut p = PackedFreelist::with_capacity(8);
+            p.set_growth_policy(GrowthPolicy::Double);
+            for i in 0..8u32 {
+                p.insert(i).unwrap();
+            }
+            p.reserve_objects(2);
+            p.insert(8).unwrap();
+            assert_eq!(16, p.capacity());
+            assert_eq!(p, (0..9).collect::<Vec<u32>>());
+        }
+    }
+
+    #[test]
+    fn remove_synced() {
+        let mut positions = PackedFreelist::with_capacity(4);
+        let mut names = PackedFreelist::with_capacity(4);
+        let mut alive = PackedFreelist::with_capacity(4);
+        let ids: Vec<AllocationID> = (0..4).map(|i| {
+            let id = positions.insert(i as f32).unwrap();
+            assert_eq!(id, names.insert(i.to_string()).unwrap());
+            assert_eq!(id, alive.insert(true).unwrap());
+            id
+        }).collect();
+
+        assert_eq!(Some(1.0), positions.remove_synced(ids[1], &mut [&mut names, &mut alive]));
+        assert_eq!(None, positions.remove_synced(ids[1], &mut [&mut names, &mut alive]));
+        assert_eq!(3, names.len());
+        assert_eq!(3, alive.len());
+        for (index, (id, _)) in positions.iter_with_ids().enumerate() {
+            assert_eq!(Some(id), names.handle_at(index));
+            assert_eq!(Some(id), alive.handle_at(index));
+        }
+        assert_eq!(names, ["0", "3", "2"].map(String::from));
+
+        let (removed, moved) = names.remove_by_id(ids[0]);
+        assert!(removed);
+        assert_eq!(Some(ids[2]), moved);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            positions.remove_synced(ids[0], &mut [&mut names]);
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn shrink_keeps_generations() {
+        let mut p = PackedFreelist::with_capacity(4);
+        let ids: Vec<AllocationID> = (0..4u32).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[2]);
+        p.remove(ids[3]);
+        assert_eq!(2, p.shrink_capacity_to(2));
+        p.remove(ids[1]);
+        assert_eq!(1, p.shrink_capacity_to(0));
+
+        p.reserve(3);
+        assert_eq!(4, p.capacity());
+        let regrown: Vec<AllocationID> = (0..3u32).map(|i| p.insert(i).unwrap()).collect();
+        for &id in &regrown {
+            assert!(!ids.contains(&id));
+        }
+        for &id in &ids[1..] {
+            assert!(!p.contains(id));
+        }
+
+        // Resetting forgets the generations of dropped allocations along with the rest.
+        for id in regrown {
+            p.remove(id);
+        }
+        assert_eq!(1, p.shrink_capacity_to(1));
+        p.reset();
+        p.reserve(4);
+        let reset: Vec<AllocationID> = (0..4u32).map(|i| p.insert(i).unwrap()).collect();
+        assert_eq!(reset, [0x10000, 0x10001, 0x10002, 0x10003]);
+    }
+
+    #[test]
+    fn iter_id_range() {
+        let mut p = PackedFreelist::with_capacity_seeded(3, 0x100);
+        let a = p.insert(1u32).unwrap();
+        let mut q = PackedFreelist::with_capacity_seeded(3, 0x200);
+        q.insert(0).unwrap();
+        let b = q.insert(2).unwrap();
+        let c = q.insert(3).unwrap();
+        p.insert_with_id(b, 2).unwrap();
+        p.insert_with_id(c, 3).unwrap();
+
+        let plugin = |n: AllocationID| (n << 16)..((n + 0x100) << 16);
+        let first: Vec<(AllocationID, &u32)> = p.iter_id_range(plugin(0x100)).collect();
+        assert_eq!(first, [(a, &1)]);
+        let second: Vec<(AllocationID, &u32)> = p.iter_id_range(plugin(0x200)).collect();
+        assert_eq!(second, [(b, &2), (c, &3)]);
+        assert_eq!(0, p.iter_id_range(plugin(0x300)).count());
+    }
+
+    #[test]
+    fn debug_slots() {
+        let mut p = PackedFreelist::with_capacity(4);
+        let ids: Vec<AllocationID> = (0..3u32).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[0]);
+
+        let slots: Vec<SlotInfo> = p.debug_slots().collect();
+        assert_eq!(slots, [
+            // The removed allocation is queued last, so its next is stale.
+            SlotInfo::Free { id: 0x10000, next: 1 },
+            SlotInfo::Live { id: 0x10001, object_index: 1 },
+            SlotInfo::Live { id: 0x10002, object_index: 0 },
+            SlotInfo::Free { id: 0x00003, next: 0 },
+        ]);
+        assert_eq!(Some(3), p.next_free_slot());
+
+        p.insert(3).unwrap();
+        p.insert(4).unwrap();
+        assert_eq!(None, p.next_free_slot());
+        assert!(p.debug_slots().all(|slot| matches!(slot, SlotInfo::Live { .. })));
+    }
+
+    #[test]
+    fn refill() {
+        let mut p = PackedFreelist::with_capacity(4);
+        let mut ids = p.refill(0..4u32).unwrap();
+        let ptr = p.as_ptr();
+        for frame in 1..4 {
+            let refilled = p.refill((0..4u32).map(|i| i * frame)).unwrap();
+            assert!(refilled.iter().all(|id| !ids.contains(id)));
+            assert!(ids.iter().all(|&id| !p.contains(id)));
+            assert_eq!(p, [0, frame, 2 * frame, 3 * frame]);
+            assert_eq!(ptr, p.as_ptr());
+            assert_eq!(4, p.capacity());
+            ids = refilled;
+        }
+
+        // the list is untouched if the objects don't fit
+        let err = p.refill(0..5u32).unwrap_err();
+        assert_eq!(AllocationErrorKind::AtCapacity, err.kind());
+        assert_eq!(p, [0, 3, 6, 9]);
+        assert!(ids.iter().all(|&id| p.contains(id)));
+
+        // or grows to fit them, following the growth policy
+        p.set_growth_policy(GrowthPolicy::Double);
+        let ids = p.refill(0..5u32).unwrap();
+        assert_eq!(8, p.capacity());
+        assert!(ids.iter().enumerate().all(|(i, &id)| p[id] == i as u32));
+    }
+
+    #[test]
+    fn split_around_id() {
+        let mut p = PackedFreelist::with_capacity(4);
+        let ids: Vec<AllocationID> = (1..5u32).map(|i| p.insert(i).unwrap()).collect();
+
+        let (object, before, after) = p.split_around_id(ids[1]).unwrap();
+        assert_eq!((*object, &before[..], &after[..]), (2, &[1][..], &[3, 4][..]));
+        *object += before.iter().chain(after.iter()).sum::<u32>();
+        before[0] = 0;
+        after[1] = 0;
+        assert_eq!(p, [0, 10, 3, 0]);
+
+        let (_, before, after) = p.split_around_id(ids[0]).unwrap();
+        assert_eq!((before.len(), after.len()), (0, 3));
+        let (_, before, after) = p.split_around_id(ids[3]).unwrap();
+        assert_eq!((before.len(), after.len()), (3, 0));
+
+        p.remove(ids[2]);
+        assert!(p.split_around_id(ids[2]).is_none());
+    }
+
+    #[test]
+    fn contains_any_u32() {
+        use self::rand::Rng;
+
+        let mut p = PackedFreelist::with_capacity(8);
+        let ids: Vec<AllocationID> = (0..8u32).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[3]);
+
+        for &id in &[0, 0xFFFF, 0xFFFE, 0x1FFFF, u32::MAX, u32::MAX - 1, ids[3]] {
+            assert!(!p.contains(id));
+        }
+        let rng = &mut rand::thread_rng();
+        for _ in 0..10000 {
+            let id: u32 = rng.gen();
+            assert_eq!(p.contains(id), ids.contains(&id) && id != ids[3]);
+        }
+        assert!(ids.iter().filter(|&&id| id != ids[3]).all(|&id| p.contains(id)));
+    }
+
+    #[test]
+    fn pool() {
+        let pool = FreelistPool::new();
+        let (ptr, first) = {
+            let mut p = pool.acquire(4);
+            p.set_growth_policy(GrowthPolicy::Double);
+            let id = p.insert(1u32).unwrap();
+            (p.as_ptr(), id)
+        };
+        assert_eq!(1, pool.idle());
+
+        let mut p = pool.acquire(2);
+        assert_eq!(0, pool.idle());
+        assert!(p.is_empty());
+        assert_eq!(4, p.capacity());
+        assert_eq!(ptr, p.as_ptr());
+        assert_ne!(first, p.insert(2).unwrap());
+        for i in 0..3 {
+            p.insert(i).unwrap();
+        }
+        assert_eq!(p.insert(3).unwrap_err().kind(), AllocationErrorKind::AtCapacity);
+        // every allocation is in use, including the one of the earlier user's object
+        assert!(!p.contains(first));
+        assert!(p.iter_with_ids().any(|(id, _)| same_slot(id, first)));
+
+        let q = pool.acquire(8);
+        assert_eq!(8, q.capacity());
+        drop(p);
+        let kept = q.into_inner();
+        assert_eq!(1, pool.idle());
+        assert_eq!(8, pool.acquire(8).capacity());
+        assert_eq!(8, kept.capacity());
+    }
+
+    #[test]
+    fn fill_n() {
+        let mut p = PackedFreelist::with_capacity(4);
+        p.insert(0u32).unwrap();
+
+        let ids = p.fill_n(3, |offset| offset as u32 * 10 + 1).unwrap();
+        assert_eq!(p, [0, 1, 11, 21]);
+        assert!(ids.iter().enumerate().all(|(offset, &id)| p.index_of(id) == Some(offset + 1)));
+
+        let mut called = false;
+        let err = p.fill_n(1, |_| { called = true; 0 }).unwrap_err();
+        assert_eq!(err.kind(), AllocationErrorKind::AtCapacity);
+        assert!(!called);
+
+        p.set_growth_policy(GrowthPolicy::Double);
+        assert_eq!(5, p.fill_n(5, |offset| offset as u32).unwrap().len());
+        assert_eq!(9, p.len());
+        assert!(p.fill_n(0, |_| unreachable!()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalidate_all_handles() {
+        let mut p = PackedFreelist::with_capacity(4);
+        let ids: Vec<AllocationID> = (0..3u32).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[0]);
+
+        p.invalidate_all_handles();
+        assert!(ids.iter().all(|&id| !p.contains(id)));
+        assert_eq!(p, [2, 1]);
+        let renewed: Vec<AllocationID> = p.iter_with_ids().map(|(id, _)| id).collect();
+        assert_eq!(renewed, [0x20002, 0x20001]);
+        assert_eq!(p[renewed[0]], 2);
+
+        // Free allocations move on when reused, as usual.
+        assert_eq!(0x10003, p.insert(3).unwrap());
+        assert_eq!(0x20000, p.insert(0).unwrap());
+
+        // The last inserted ID follows its object.
+        p.invalidate_all_handles();
+        assert_eq!(p.last_inserted_id(), p.handle_at(3));
+        assert_eq!(p[p.last_inserted_id().unwrap()], 0);
+    }
+
+    #[test]
+    fn insert_mut() {
+        let mut p = PackedFreelist::with_capacity(2);
+        let a = p.insert(1u32).unwrap();
+        p.remove(a);
+        let b = p.insert(2).unwrap();
+
+        let (c, value) = p.insert_mut(3).unwrap();
+        *value *= 10;
+        assert_eq!(p[c], 30);
+        assert_eq!(p[b], 2);
+        assert_eq!(p.insert_mut(4).unwrap_err().kind(), AllocationErrorKind::AtCapacity);
+    }
+
+    #[test]
+    fn remove_all() {
+        let mut p = PackedFreelist::with_capacity(4);
+        let ids: Vec<AllocationID> = (0..3u32).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[1]);
+
+        assert_eq!(2, p.remove_all());
+        assert_eq!(0, p.len());
+        assert_eq!(4, p.capacity());
+        assert!(ids.iter().all(|&id| !p.contains(id)));
+        assert_eq!(0, p.remove_all());
+
+        let reused: Vec<AllocationID> = (0..4u32).map(|i| p.insert(i).unwrap()).collect();
+        assert!(reused.iter().all(|id| !ids.contains(id)));
+    }
+
+    #[test]
+    fn dense_index() {
+        let mut p = PackedFreelist::with_capacity(8);
+        let a = p.insert(0u32).unwrap();
+        assert_eq!(None, p.dense_index(a));
+
+        let b = p.insert(1).unwrap();
+        p.enable_dense_indices();
+        let c = p.insert(2).unwrap();
+        assert_eq!([a, b, c].map(|id| p.dense_index(id)), [Some(0), Some(1), Some(2)]);
+
+        // Removing `a` moves `c` in packed order, but not in dense order.
+        p.remove(a);
+        assert_eq!(Some(2), p.dense_index(c));
+        assert_eq!(None, p.dense_index(a));
+        let d = p.insert(3).unwrap();
+        assert_eq!(Some(0), p.dense_index(d));
+
+        let checkpoint = p.checkpoint();
+        let e = p.insert(4).unwrap();
+        assert_eq!(Some(3), p.dense_index(e));
+        p.rollback(checkpoint).unwrap();
+        p.remove_all();
+        let ids: Vec<AllocationID> = (0..4u32).map(|i| p.insert(i).unwrap()).collect();
+        let mut dense: Vec<usize> = ids.iter().map(|&id| p.dense_index(id).unwrap()).collect();
+        dense.sort_unstable();
+        assert_eq!(dense, [0, 1, 2, 3]);
+
+        p.reset();
+        let f = p.insert(5).unwrap();
+        assert_eq!(Some(0), p.dense_index(f));
+    }
+
+    #[test]
+    fn get_cloned() {
+        let mut p = PackedFreelist::with_capacity(2);
+        let a = p.insert(String::from("a")).unwrap();
+        let value = p.get_cloned(a).unwrap();
+        p.remove(a);
+        assert_eq!(value, "a");
+        assert_eq!(None, p.get_cloned(a));
+
+        let mut q = PackedFreelist::with_capacity(2);
+        let b = q.insert(1u32).unwrap();
+        let c = q.insert(q.get_copied(b).unwrap() + 1).unwrap();
+        assert_eq!(Some(2), q.get_copied(c));
+        q.remove(c);
+        assert_eq!(None, q.get_copied(c));
+    }
+
+    #[test]
+    fn metadata() {
+        let mut p: PackedFreelist<u32, u8> = PackedFreelist::with_capacity_and_metadata(8);
+        let ids: Vec<AllocationID> = (0..6u32).map(|i| p.insert_with_meta(i, i as u8 * 10).unwrap()).collect();
+        let defaulted = p.insert(6).unwrap();
+        assert_eq!(Some(&0), p.meta(defaulted));
+        *p.meta_mut(defaulted).unwrap() = 60;
+
+        let in_step = |p: &PackedFreelist<u32, u8>| p.iter().zip(p.metadata()).all(|(&value, &meta)| meta as u32 == value * 10);
+
+        p.remove(ids[0]);
+        assert!(in_step(&p));
+        assert_eq!(None, p.meta(ids[0]));
+        p.shift_remove(ids[2]);
+        assert!(in_step(&p));
+        p.rotate_left(2);
+        assert!(in_step(&p));
+        p.partition_in_place(|&value| value % 2 == 0);
+        assert!(in_step(&p));
+        p.retain_stable(|&value| value != 5);
+        assert!(in_step(&p));
+        p.drain_while(|&value| value != 3);
+        assert!(in_step(&p));
+        assert!(p.iter_with_ids().all(|(id, &value)| p.meta(id) == Some(&(value as u8 * 10))));
+
+        let checkpoint = p.checkpoint();
+        p.insert_with_meta(7, 70).unwrap();
+        p.rollback(checkpoint).unwrap();
+        assert_eq!(p.len(), p.metadata().len());
+        p.remove_all();
+        assert!(p.metadata().is_empty());
+    }
+
+    #[test]
+    fn shrink_to() {
+        let mut p = PackedFreelist::with_capacity(1000);
+        let ids: Vec<AllocationID> = (0..1000u32).map(|i| p.insert(i).unwrap()).collect();
+        for &id in &ids[10..] {
+            p.remove(id);
+        }
+
+        p.shrink_to(100);
+        assert_eq!(100, p.capacity());
+        p.shrink_to(200);
+        assert_eq!(100, p.capacity());
+        assert!(ids[..10].iter().enumerate().all(|(i, &id)| p[id] == i as u32));
+        let refilled: Vec<AllocationID> = (0..90u32).map(|i| p.insert(i).unwrap()).collect();
+        assert!(refilled.iter().all(|id| !ids.contains(id)));
+        assert!(p.insert(90).is_err());
+
+        // Objects in high allocations hold the capacity up.
+        p.remove_many(ids[..9].iter().cloned().chain(refilled));
+        p.shrink_to_fit();
+        assert_eq!(10, p.capacity());
+        assert_eq!(p, [9]);
+    }
+
+    #[test]
+    fn from_map() {
+        let map: std::collections::HashMap<String, u32> = (0..10u32).map(|i| (i.to_string(), i)).collect();
+        let (p, ids) = PackedFreelist::from_map(map.clone());
+        assert_eq!(10, p.len());
+        assert_eq!(10, p.capacity());
+        assert_eq!(10, ids.len());
+        assert!(map.iter().all(|(key, &value)| p[ids[key]] == value));
+    }
+
+    #[test]
+    fn zip_mut() {
+        let mut positions = PackedFreelist::with_capacity(4);
+        let mut velocities = PackedFreelist::with_capacity(4);
+        let ids: Vec<AllocationID> = (0..4).map(|i| {
+            let id = positions.insert(i as f32).unwrap();
+            velocities.insert_with_id(id, 1.0f32).unwrap();
+            id
+        }).collect();
+        velocities.remove(ids[1]);
+        positions.remove(ids[0]);
+
+        let mut visited = Vec::new();
+        for (id, position, velocity) in positions.zip_mut(&mut velocities) {
+            *position += *velocity;
+            *velocity = 0.0;
+            visited.push(id);
+        }
+        assert_eq!(visited, [ids[3], ids[2]]);
+        assert_eq!(positions, [4.0, 1.0, 3.0]);
+        assert_eq!(velocities, [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn try_reserve() {
+        const MAX : usize = PackedFreelist::<u32>::MAX_SIZE;
+        let mut p = PackedFreelist::with_capacity(2);
+        p.insert(0u32).unwrap();
+
+        assert_eq!(Ok(()), p.try_reserve(4));
+        assert_eq!(5, p.capacity());
+        assert_eq!(Ok(()), p.try_reserve(1));
+        assert_eq!(5, p.capacity());
+
+        let err = p.try_reserve(MAX).unwrap_err();
+        assert_eq!(err, ReserveError::ExceedsMaxSize { requested: MAX + 1, max: MAX });
+        assert!(err.to_string().contains("max size"));
+        assert_eq!(5, p.capacity());
+    }
+
+    #[test]
+    fn rebalance_generations() {
+        let mut p = PackedFreelist::with_capacity(4);
+        let a = p.insert(0u32).unwrap();
+        let b = p.insert(1).unwrap();
+        for i in 0..10 {
+            let x = p.insert(i).unwrap();
+            p.remove(x);
+        }
+        let c = p.insert(2).unwrap();
+        let d = p.insert(3).unwrap();
+        p.remove(a);
+        p.remove(b);
+        assert_eq!((c >> 16, d >> 16), (6, 6));
+
+        let remap = p.rebalance_generations();
+        assert_eq!(2, remap.len());
+        assert!(!p.contains(c) && !p.contains(d));
+        assert_eq!((p[remap[&c]], p[remap[&d]]), (2, 3));
+        assert_eq!(p, [3, 2]);
+        assert_eq!(p.live_slot_indices().collect::<Vec<usize>>(), [1, 0]);
+        assert!(remap.values().all(|&id| id >> 16 == 2));
+
+        // The hot allocations are reused last, in order of generation.
+        assert_eq!(Some(2), p.next_free_slot());
+        assert!(p.rebalance_generations().is_empty());
+    }
+
+    #[test]
+    fn try_from_vec() {
+        use std::convert::TryFrom;
+
+        let mut p = PackedFreelist::try_from(vec![1u32, 2, 3]).unwrap();
+        assert_eq!(p, [1, 2, 3]);
+        assert_eq!(3, p.capacity());
+        let ids: Vec<AllocationID> = p.iter_with_ids().map(|(id, _)| id).collect();
+        p.remove(ids[0]);
+        assert_eq!(p, [3, 2]);
+        assert!(p.insert(4).is_ok());
+
+        assert!(PackedFreelist::<u32>::try_from(Vec::new()).unwrap().is_empty());
+
+        // Zero sized objects take no memory, however many there are.
+        const MAX : usize = PackedFreelist::<()>::MAX_SIZE;
+        assert_eq!(MAX, PackedFreelist::try_from(vec![(); MAX]).unwrap().len());
+        let err = PackedFreelist::try_from(vec![(); MAX + 1]).unwrap_err();
+        assert_eq!(err, ReserveError::ExceedsMaxSize { requested: MAX + 1, max: MAX });
+    }
+
+    #[test]
+    fn iter_indexed() {
+        let mut p = PackedFreelist::with_capacity(4);
+        let ids: Vec<AllocationID> = (0..4u32).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(ids[1]);
+
+        let indexed: Vec<(usize, AllocationID, u32)> = p.iter_indexed().map(|(i, id, &v)| (i, id, v)).collect();
+        assert_eq!(indexed, [(0, ids[0], 0), (1, ids[3], 3), (2, ids[2], 2)]);
+        assert!(p.iter_indexed().all(|(i, id, &v)| p.index_of(id) == Some(i) && p.get_at(i) == Some(&v)));
+
+        let reversed: Vec<usize> = p.iter_indexed().rev().map(|(i, _, _)| i).collect();
+        assert_eq!(reversed, [2, 1, 0]);
+        let mut both_ends = p.iter_indexed();
+        assert_eq!(3, both_ends.len());
+        assert_eq!(Some(2), both_ends.next_back().map(|(i, _, _)| i));
+        assert_eq!(Some(0), both_ends.next().map(|(i, _, _)| i));
+        assert_eq!(1, both_ends.len());
+    }
+
+    #[test]
+    fn get() {
+        let mut p = PackedFreelist::with_capacity(2);
+        let a = p.insert(1u32).unwrap();
+        let b = p.insert(2).unwrap();
+        assert_eq!(Some(&1), p.get(a));
+        assert_eq!(Some(&2), p.get(b));
+
+        p.remove(a);
+        assert_eq!(None, p.get(a));
+        let c = p.insert(3).unwrap();
+        assert!(same_slot(a, c));
+        assert_eq!(None, p.get(a));
+        assert_eq!(Some(&3), p.get(c));
+        assert_eq!(None, p.get(0xFFFF));
+        assert_eq!(None, p.get(u32::MAX));
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut p = PackedFreelist::with_capacity(3);
+        let a = p.insert(1u32).unwrap();
+        let b = p.insert(2).unwrap();
+        let ptr = p.as_ptr();
+
+        *p.get_mut(b).unwrap() += 10;
+        assert_eq!(p, [1, 12]);
+        assert_eq!(ptr, p.as_ptr());
+        assert!(p.contains(b));
+
+        p.remove(a);
+        assert_eq!(None, p.get_mut(a));
+        *p.get_mut(b).unwrap() += 10;
+        assert_eq!(p[b], 22);
+    }
+
+    #[test]
+    fn index_mut() {
+        let mut p = PackedFreelist::with_capacity(2);
+        let a = p.insert(TestStruct { n: 1 }).unwrap();
+        let b = p.insert(TestStruct { n: 2 }).unwrap();
+        p[a].n += 10;
+        p[b] = TestStruct { n: 20 };
+        assert_eq!((p[a].n, p[b].n), (11, 20));
+
+        p.remove(a);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            p[a].n = 0;
+        }));
+        assert!(result.is_err());
+        assert_eq!(p[b].n, 20);
+    }
+
+    #[test]
+    fn remove_returns_value() {
+        let mut p : PackedFreelist<String> = PackedFreelist::with_capacity(2);
+        let a = p.insert("a".to_string()).unwrap();
+        let b = p.insert("b".to_string()).unwrap();
+
+        assert_eq!(p.remove(a), Some("a".to_string()));
+        assert_eq!(p.remove(a), None);
+        assert_eq!(p, ["b".to_string()]);
+
+        // a's allocation is reused, but a is stale
+        let c = p.insert("c".to_string()).unwrap();
+        assert_eq!(c & 0xffff, a & 0xffff);
+        assert_eq!(p.remove(a), None);
+        assert_eq!(p.remove(0xffff), None);
+        assert_eq!(p.len(), 2);
+
+        assert_eq!(p.remove(b), Some("b".to_string()));
+        assert_eq!(p.remove(c), Some("c".to_string()));
+        assert!(p.is_empty());
+    }
+
+    #[test]
+    fn with_capacity_zero() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(0);
+        assert_eq!(p.len(), 0);
+        assert_eq!(p.capacity(), 0);
+        assert!(p.is_empty());
+        assert!([0, 1, 0x10000, 0xffff, u32::MAX].iter().all(|&id| !p.contains(id)));
+        assert_eq!(p.remove(0), None);
+
+        assert_eq!(p.insert(1).unwrap_err().kind(), AllocationErrorKind::AtCapacity);
+        assert_eq!(p.len(), 0);
+        assert_eq!(p.capacity(), 0);
+
+        p.reserve(1);
+        let a = p.insert(1).unwrap();
+        assert_eq!(p[a], 1);
+
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(0);
+        p.set_growth_policy(GrowthPolicy::Double);
+        let ids: Vec<AllocationID> = (0..3).map(|i| p.insert(i).unwrap()).collect();
+        assert_eq!(p.capacity(), 4);
+        assert!(ids.iter().enumerate().all(|(i, &id)| p[id] == i as u32));
+
+        let (p, ids) = PackedFreelist::<u32>::from_map(std::collections::HashMap::<u32, u32>::new());
+        assert!(p.is_empty() && ids.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "allocation was reused")]
+    fn index_stale_id() {
+        let mut p = PackedFreelist::with_capacity(1);
+        let a = p.insert(TestStruct { n: 1 }).unwrap();
+        p.remove(a);
+        let b = p.insert(TestStruct { n: 2 }).unwrap();
+        assert_eq!(a & 0xffff, b & 0xffff);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            p[a].n = 0;
+        }));
+        assert!(result.is_err());
+        assert_eq!(p[b].n, 2);
+
+        let _ = p[a].n;
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut p = PackedFreelist::with_capacity(3);
+        let a = p.insert(TestStruct { n: 1 }).unwrap();
+        let b = p.insert(TestStruct { n: 2 }).unwrap();
+        let c = p.insert(TestStruct { n: 3 }).unwrap();
+        p.remove(a);
+
+        assert_eq!(p.iter_mut().len(), 2);
+        for object in p.iter_mut() {
+            object.n *= 10;
+        }
+        assert_eq!((p[b].n, p[c].n), (20, 30));
+        assert_eq!(p.iter_mut().rev().map(|object| object.n).collect::<Vec<_>>(), [20, 30]);
+    }
+
+    #[test]
+    fn clear() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(4);
+        let old: Vec<AllocationID> = (0..3).map(|i| p.insert(i).unwrap()).collect();
+        p.remove(old[1]);
+
+        p.clear();
+        assert_eq!(p.len(), 0);
+        assert_eq!(p.capacity(), 4);
+        assert!(old.iter().all(|&id| !p.contains(id)));
+
+        let new: Vec<AllocationID> = (0..4).map(|i| p.insert(i).unwrap()).collect();
+        assert!(p.insert(4).is_err());
+        assert!(old.iter().all(|&id| !p.contains(id)));
+        assert!(new.iter().enumerate().all(|(i, &id)| p[id] == i as u32));
+    }
+
+    #[test]
+    fn retain() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(10);
+        let ids: Vec<AllocationID> = (0..10).map(|i| p.insert(i).unwrap()).collect();
+
+        let mut calls = 0;
+        p.retain(|&n| {
+            calls += 1;
+            n % 2 == 0
+        });
+        assert_eq!(calls, 10);
+        assert_eq!(p.len(), 5);
+
+        let mut kept = p.to_vec();
+        kept.sort();
+        assert_eq!(kept, [0, 2, 4, 6, 8]);
+        for (i, &id) in ids.iter().enumerate() {
+            if i % 2 == 0 {
+                assert_eq!(p[id], i as u32);
+            } else {
+                assert!(!p.contains(id));
+            }
+        }
+    }
+
+    #[test]
+    fn iter_with_ids_mut() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        assert_eq!(p.iter_with_ids_mut().len(), 0);
+        assert!(p.iter_with_ids_mut().next().is_none());
+
+        let a = p.insert(1).unwrap();
+        let b = p.insert(2).unwrap();
+        let c = p.insert(3).unwrap();
+        p.remove(a);
+
+        assert_eq!(p.iter_with_ids_mut().len(), 2);
+        let mut visited = Vec::new();
+        for (id, object) in p.iter_with_ids_mut() {
+            visited.push(id);
+            *object *= 10;
+        }
+        assert_eq!(visited, [c, b]);
+        assert_eq!((p[b], p[c]), (20, 30));
+
+        let (id, object) = p.iter_with_ids_mut().next_back().unwrap();
+        assert_eq!(id, b);
+        *object += 1;
+        assert_eq!(p.get(b), Some(&21));
+        assert!(p.iter_with_ids().all(|(id, _)| p.contains(id)));
+    }
+
+    #[test]
+    fn rollback_after_rotate() {
+        for rotate_left in [true, false] {
+            let mut p : PackedFreelist<&str> = PackedFreelist::with_capacity(3);
+            let a = p.insert("a").unwrap();
+            let cp = p.checkpoint();
+            let b = p.insert("b").unwrap();
+            let c = p.insert("c").unwrap();
+            if rotate_left {
+                p.rotate_left(1);
+            } else {
+                p.rotate_right(1);
+            }
+
+            // the objects inserted since the checkpoint are no longer at its end
+            assert!(p.rollback(cp).is_err());
+            assert_eq!(p.len(), 3);
+            assert_eq!((p[a], p[b], p[c]), ("a", "b", "c"));
+        }
+    }
+
+    #[test]
+    fn rollback_after_partition() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(5);
+        let a = p.insert(1).unwrap();
+        let b = p.insert(3).unwrap();
+        let cp = p.checkpoint();
+        let c = p.insert(0).unwrap();
+        let d = p.insert(2).unwrap();
+
+        // the even objects inserted since the checkpoint move in front of the odd ones
+        assert_eq!(p.partition_in_place(|n| n % 2 == 0), 2);
+        assert!(p.rollback(cp).is_err());
+        assert_eq!(p.len(), 4);
+        assert_eq!((p[a], p[b], p[c], p[d]), (1, 3, 0, 2));
+
+        // a partition that moves nothing leaves the checkpoint valid
+        let cp = p.checkpoint();
+        let e = p.insert(5).unwrap();
+        p.partition_in_place(|n| n % 2 == 0);
+        assert!(p.rollback(cp).is_ok());
+        assert!(!p.contains(e));
+        assert_eq!(p.len(), 4);
+    }
+
+    #[test]
+    fn insert_with_id_rejects_old_generations() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+        let a = p.insert(1).unwrap();
+        p.remove(a);
+        let b = p.insert(2).unwrap();
+        p.remove(b);
+
+        // a is behind b, the allocation's current generation
+        assert_eq!(p.insert_with_id(a, 3), Err(3));
+        assert!(p.is_empty());
+        let c = p.insert(4).unwrap();
+        assert_ne!(c, b);
+        assert!(!p.contains(a) && !p.contains(b));
+        p.remove(c);
+
+        // the current generation is accepted
+        assert_eq!(p.insert_with_id(c, 5), Ok(()));
+        assert_eq!(p[c], 5);
+        p.remove(c);
+        assert_ne!(p.insert(6).unwrap(), c);
+
+        // allocations dropped by shrinking keep their generations
+        let mut q : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+        q.insert(1).unwrap();
+        let d = q.insert(2).unwrap();
+        q.remove(d);
+        let e = q.insert(3).unwrap();
+        q.remove(e);
+        q.shrink_to_fit();
+        assert_eq!(q.capacity(), 1);
+        assert_eq!(q.insert_with_id(d, 4), Err(4));
+        assert_eq!(q.capacity(), 1);
+        assert_eq!(q.insert_with_id(e + 0x10000, 4), Ok(()));
+    }
+
+    #[test]
+    fn allocate_overflowing_count() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+        p.set_growth_policy(GrowthPolicy::Double);
+        p.insert(1).unwrap();
+
+        let err = p.fill_n(usize::MAX, |_| 0).unwrap_err();
+        assert_eq!(err.kind(), AllocationErrorKind::AtCapacity);
+        assert_eq!(p.reserve_ids(usize::MAX).unwrap_err().kind(), AllocationErrorKind::AtCapacity);
+        assert_eq!(p.len(), 1);
+        assert_eq!(p.capacity(), 2);
+    }
+
+    #[test]
+    fn constructors_with_metadata() {
+        let (p, a) = PackedFreelist::singleton_with_meta(1u32, "one");
+        assert_eq!((p[a], p.meta(a)), (1, Some(&"one")));
+
+        let mut q : PackedFreelist<f32, u8> = PackedFreelist::with_capacity_seeded_and_metadata(2, 7);
+        let b = q.insert(2.0).unwrap();
+        assert_eq!(b >> 16, 8);
+
+        let mut r : PackedFreelist<u32, &str> = PackedFreelist::with_capacity_and_metadata(1);
+        r.insert_with_id(b, 5).unwrap();
+        let (x, y) = get_pair_mut(&mut r, &mut q, b).unwrap();
+        *x += 1;
+        *y += 1.0;
+        assert_eq!((r[b], q[b]), (6, 3.0));
+
+        let map: std::collections::HashMap<&str, u32> = [("a", 1), ("b", 2)].iter().cloned().collect();
+        let (s, ids) = PackedFreelist::<u32, bool>::from_map_and_metadata(map);
+        assert_eq!((s[ids["a"]], s[ids["b"]]), (1, 2));
+        assert_eq!(s.meta(ids["a"]), Some(&false));
+    }
+
+    #[test]
+    fn debug_summary() {
+        let mut p = PackedFreelist::with_capacity(4);
+        let a = p.insert(1u32).unwrap();
+        p.insert(2).unwrap();
+        p.remove(a);
+        p.insert(3).unwrap();
+
+        assert_eq!(format!("{:?}", p),
+                   "PackedFreelist { len: 2, capacity: 4, total_inserts: 3, total_removes: 1, objects: {0x10001: 2, 0x10002: 3} }");
+    }
+
+    #[test]
+    fn rollback_after_growth_with_spare_capacity() {
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(1);
+            p.set_growth_policy(GrowthPolicy::Double);
+            let cp = p.checkpoint();
+            assert!(p.insert(1).is_ok());
+            assert!(p.insert(2).is_ok());
+            assert_eq!(p.capacity(), 2);
+            assert!(p.rollback(cp).is_ok());
+            assert!(p.is_empty());
+            let ids: Vec<AllocationID> = (0..2).map(|v| p.insert(v).unwrap()).collect();
+            assert!(ids.iter().all(|&id| p.contains(id)));
+        }
+
+        {
+            let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(2);
+            p.set_growth_policy(GrowthPolicy::Additive(2));
+            let cp = p.checkpoint();
+            for v in 0..3 {
+                assert!(p.insert(v).is_ok());
+            }
+            assert_eq!(p.capacity(), 4);
+            assert!(p.rollback(cp).is_ok());
+            assert!(p.is_empty());
+            let ids: Vec<AllocationID> = (0..4).map(|v| p.insert(v).unwrap()).collect();
+            assert!(ids.iter().all(|&id| p.contains(id)));
+        }
+    }
+
+    #[test]
+    fn rollback_after_shrink() {
+        let mut p : PackedFreelist<u32> = PackedFreelist::with_capacity(8);
+        let cp = p.checkpoint();
+        assert!(p.insert(1).is_ok());
+        assert_eq!(p.shrink_capacity_to(4), 4);
+        assert!(p.rollback(cp).is_err());
+        assert_eq!(p, [1]);
+    }
 }